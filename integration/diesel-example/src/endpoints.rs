@@ -30,7 +30,7 @@ async fn create_post(ctx: &mut Context<State>) -> Result {
                 Ok(posts.order(dsl::id.desc()).first(&conn)?)
             })
         })
-        .await?;
+        .await??;
     ctx.resp.status = StatusCode::CREATED;
     ctx.write_json(&post)
 }
@@ -84,7 +84,7 @@ impl Mutation {
                     Ok(posts.order(dsl::id.desc()).first(&conn)?)
                 })
             })
-            .await?;
+            .await??;
         Ok(post)
     }
 
@@ -1,7 +1,9 @@
+use std::future::Future;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::Arc;
+use std::time::Duration;
 
-use roa::{App, Endpoint, Executor, Server, State};
+use roa::{App, Endpoint, Executor, Graceful, Server, State};
 
 use super::TcpIncoming;
 
@@ -41,18 +43,29 @@ pub trait Listener {
     /// }
     /// ```
     fn run(self) -> std::io::Result<(SocketAddr, Self::Server)>;
+
+    /// Listen on a socket addr, return a graceful server and the real addr it binds.
+    ///
+    /// The server stops accepting new connections once `signal` resolves,
+    /// then waits up to `drain_timeout` for in-flight requests and spawned tasks.
+    fn bind_graceful(
+        self,
+        addr: impl ToSocketAddrs,
+        signal: impl 'static + Send + Future<Output = ()>,
+        drain_timeout: Duration,
+    ) -> std::io::Result<(SocketAddr, Graceful)>;
 }
 
 impl<S, E> Listener for App<S, Arc<E>>
 where
-    S: State,
-    E: for<'a> Endpoint<'a, S>,
+    S: State + Send + Sync,
+    E: Send + Sync + for<'a> Endpoint<'a, S>,
 {
     type Server = Server<TcpIncoming, Self, Executor>;
     fn bind(self, addr: impl ToSocketAddrs) -> std::io::Result<(SocketAddr, Self::Server)> {
         let incoming = TcpIncoming::bind(addr)?;
         let local_addr = incoming.local_addr();
-        Ok((local_addr, self.accept(incoming)))
+        Ok((local_addr, self.try_accept(incoming)?))
     }
 
     fn listen(
@@ -68,6 +81,20 @@ where
     fn run(self) -> std::io::Result<(SocketAddr, Self::Server)> {
         self.bind("127.0.0.1:0")
     }
+
+    fn bind_graceful(
+        self,
+        addr: impl ToSocketAddrs,
+        signal: impl 'static + Send + Future<Output = ()>,
+        drain_timeout: Duration,
+    ) -> std::io::Result<(SocketAddr, Graceful)> {
+        let incoming = TcpIncoming::bind(addr)?;
+        let local_addr = incoming.local_addr();
+        Ok((
+            local_addr,
+            self.accept_graceful(incoming, signal, drain_timeout),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -9,7 +9,7 @@ use std::{fmt, io, matches};
 use async_std::net::{SocketAddr, TcpListener, TcpStream};
 use futures_timer::Delay;
 use roa::stream::AsyncStream;
-use roa::{Accept, AddrStream};
+use roa::{Accept, AddrStream, ConnInfo};
 use tracing::{debug, error, trace};
 
 /// A stream of connections from binding to an address.
@@ -147,7 +147,13 @@ impl Accept for TcpIncoming {
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
         let (stream, addr) = futures::ready!(self.poll_stream(cx))?;
-        Poll::Ready(Some(Ok(AddrStream::new(addr, AsyncStream(stream)))))
+        let mut info = ConnInfo::new();
+        if let Ok(local_addr) = stream.local_addr() {
+            info = info.with_local_addr(local_addr);
+        }
+        Poll::Ready(Some(Ok(
+            AddrStream::new(addr, AsyncStream(stream)).with_info(info)
+        )))
     }
 }
 
@@ -1,5 +1,6 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use roa::Spawn;
 
@@ -29,6 +30,11 @@ impl Spawn for Exec {
     fn spawn_blocking(&self, task: BlockingObj) {
         async_std::task::spawn_blocking(task);
     }
+
+    #[inline]
+    fn sleep(&self, duration: Duration) -> FutureObj {
+        Box::pin(async_std::task::sleep(duration))
+    }
 }
 
 #[cfg(test)]
@@ -50,4 +56,42 @@ mod tests {
         assert_eq!(StatusCode::OK, resp.status());
         Ok(())
     }
+
+    #[async_std::test]
+    async fn timeout() -> Result<(), Box<dyn Error>> {
+        use std::time::Duration;
+
+        use roa::test::TestClient;
+        use roa::timeout::Timeout;
+        use roa::Context;
+
+        async fn slow(ctx: &mut Context) -> roa::Result {
+            ctx.exec.sleep(Duration::from_secs(1)).await;
+            Ok(())
+        }
+
+        let app = App::with_exec((), Exec)
+            .gate(Timeout::new(Duration::from_millis(50)))
+            .end(slow);
+        let resp = TestClient::new(app).get("/").send().await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, resp.status);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn join_handle() {
+        use roa::Context;
+
+        async fn end(ctx: &mut Context) -> roa::Result {
+            let handle = ctx.exec.spawn(futures::future::pending::<()>());
+            handle.abort();
+            assert!(handle.await.unwrap_err().is_cancelled());
+            ctx.exec.spawn_blocking(|| panic!("boom")).await?;
+            Ok(())
+        }
+
+        let app = App::with_exec((), Exec).end(end);
+        let resp = roa::test::TestClient::new(app).get("/").send().await;
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, resp.status);
+    }
 }
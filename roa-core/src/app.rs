@@ -1,4 +1,9 @@
+mod config;
 mod future;
+mod graceful;
+mod lifecycle;
+#[cfg(feature = "runtime")]
+mod local;
 #[cfg(feature = "runtime")]
 mod runtime;
 mod stream;
@@ -6,21 +11,28 @@ mod stream;
 use std::convert::Infallible;
 use std::error::Error;
 use std::future::Future;
+use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
+use std::time::Duration;
 
 use future::SendFuture;
-use http::{Request as HttpRequest, Response as HttpResponse};
+use futures::channel::oneshot;
+use http::{Request as HttpRequest, Response as HttpResponse, StatusCode};
 use hyper::service::Service;
 use hyper::{Body as HyperBody, Server};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+pub use self::config::HttpConfig;
+pub use self::graceful::Graceful;
+use self::lifecycle::Lifecycle;
+pub use self::lifecycle::Shutdown;
 pub use self::stream::AddrStream;
 use crate::{
-    Accept, Chain, Context, Endpoint, Executor, Middleware, MiddlewareExt, Request, Response,
-    Spawn, State,
+    default_error_handler, Accept, Body, Chain, ConnInfo, Context, Endpoint, ErrorHandler,
+    Executor, Middleware, MiddlewareExt, Request, Response, Spawn, State, Status,
 };
 
 /// The Application of roa.
@@ -86,13 +98,23 @@ pub struct App<S, T> {
     service: T,
     exec: Executor,
     state: S,
+    error_handler: Option<SharedErrorHandler<S>>,
+    body_limit: Option<u64>,
+    http_config: HttpConfig,
+    lifecycle: Lifecycle<S>,
 }
 
+/// A shared error handler.
+type SharedErrorHandler<S> = Arc<dyn for<'a> ErrorHandler<'a, S>>;
+
 /// An implementation of hyper HttpService.
 pub struct HttpService<S, E> {
     endpoint: Arc<E>,
     remote_addr: SocketAddr,
+    conn: ConnInfo,
     exec: Executor,
+    error_handler: Option<SharedErrorHandler<S>>,
+    body_limit: Option<u64>,
     pub(crate) state: S,
 }
 
@@ -103,13 +125,113 @@ impl<S, T> App<S, T> {
             exec,
             state,
             service,
+            error_handler,
+            body_limit,
+            http_config,
+            lifecycle,
         } = self;
         App {
             service: mapper(service),
             exec,
             state,
+            error_handler,
+            body_limit,
+            http_config,
+            lifecycle,
         }
     }
+
+    /// Set the configuration of http server, like keep-alive, buffer size and HTTP/2 options.
+    ///
+    /// It's applied to servers constructed by `App::accept` and `App::serve_local`,
+    /// so it works for all listeners.
+    pub fn http_config(mut self, config: HttpConfig) -> Self {
+        self.http_config = config;
+        self
+    }
+
+    /// Set the limit of request body size in bytes for all requests.
+    ///
+    /// Reading a body over the limit by `Request::stream` or `Request::reader` fails,
+    /// and 413 PAYLOAD TOO LARGE will be responded.
+    /// It can be overridden by `Request::set_body_limit` in middlewares.
+    pub fn body_limit(mut self, limit: u64) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// Add a hook called with state and executor before serving, like warming caches
+    /// or running migrations. Hooks are called in order, and the server fails to start
+    /// if any of them fails.
+    ///
+    /// Lifecycle hooks and background tasks are only managed by servers with graceful shutdown,
+    /// constructed by `App::accept_graceful`. Other servers refuse to start if any is registered.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Executor, Result};
+    ///
+    /// async fn warm_up(_state: (), _exec: Executor) -> Result {
+    ///     // load caches
+    ///     Ok(())
+    /// }
+    ///
+    /// let app = App::new().on_start(warm_up).end(());
+    /// ```
+    pub fn on_start<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(S, Executor) -> Fut,
+        Fut: 'static + Send + Future<Output = crate::Result>,
+    {
+        self.lifecycle.on_start(hook);
+        self
+    }
+
+    /// Add a hook called with state and executor after the server is drained,
+    /// like flushing buffers or closing pools. Hooks are called in order, and failures are logged.
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(S, Executor) -> Fut,
+        Fut: 'static + Send + Future<Output = crate::Result>,
+    {
+        self.lifecycle.on_shutdown(hook);
+        self
+    }
+
+    /// Add a background task bound to the server lifetime.
+    ///
+    /// It's spawned after start hooks, and the `Shutdown` is fired once shutdown signal is received.
+    /// The server waits for the task to return while draining,
+    /// and it's cancelled if the drain timeout elapses.
+    pub fn background<F, Fut>(mut self, task: F) -> Self
+    where
+        F: 'static + Send + Sync + Fn(S, Shutdown) -> Fut,
+        Fut: 'static + Send + Future<Output = crate::Result>,
+    {
+        self.lifecycle.background(task);
+        self
+    }
+
+    /// Set an error handler to render the status thrown by the top middleware.
+    /// The `default_error_handler` is used if not set.
+    ///
+    /// Headers of the status are applied to response before the handler is called.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, Status};
+    ///
+    /// async fn handle_error(ctx: &mut Context, status: Status) {
+    ///     ctx.resp.status = status.status_code;
+    ///     ctx.resp.write(format!("error: {}", status.message));
+    /// }
+    ///
+    /// let app = App::new().on_error(handle_error).end(());
+    /// ```
+    pub fn on_error(mut self, handler: impl for<'a> ErrorHandler<'a, S>) -> Self {
+        self.error_handler = Some(Arc::new(handler));
+        self
+    }
 }
 
 impl<S> App<S, ()> {
@@ -117,8 +239,12 @@ impl<S> App<S, ()> {
     pub fn with_exec(state: S, exec: impl 'static + Send + Sync + Spawn) -> Self {
         Self {
             service: (),
-            exec: Executor(Arc::new(exec)),
+            exec: Executor::new(exec),
             state,
+            error_handler: None,
+            body_limit: None,
+            http_config: HttpConfig::default(),
+            lifecycle: Lifecycle::default(),
         }
     }
 }
@@ -149,20 +275,134 @@ where
     E: for<'a> Endpoint<'a, S>,
 {
     /// Construct a hyper server by an incoming.
+    ///
+    /// Lifecycle hooks and background tasks only run with `accept_graceful`,
+    /// use `try_accept` to get an error instead of a panic if any is registered.
+    ///
+    /// ### Panics
+    /// Panics if any lifecycle hook or background task is registered,
+    /// or any timer option of `HttpConfig` is set out of a tokio runtime.
     pub fn accept<I, IO>(self, incoming: I) -> Server<I, Self, Executor>
     where
-        S: State,
+        S: State + Send + Sync,
+        E: Send + Sync,
         IO: 'static + Send + Sync + Unpin + AsyncRead + AsyncWrite,
         I: Accept<Conn = AddrStream<IO>>,
         I::Error: Into<Box<dyn Error + Send + Sync>>,
     {
-        Server::builder(incoming)
+        self.try_accept(incoming)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Construct a hyper server by an incoming.
+    /// Fail if any lifecycle hook or background task is registered,
+    /// they only run with `accept_graceful`,
+    /// or any timer option of `HttpConfig` is set out of a tokio runtime.
+    pub fn try_accept<I, IO>(self, incoming: I) -> io::Result<Server<I, Self, Executor>>
+    where
+        S: State + Send + Sync,
+        E: Send + Sync,
+        IO: 'static + Send + Sync + Unpin + AsyncRead + AsyncWrite,
+        I: Accept<Conn = AddrStream<IO>>,
+        I::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.lifecycle.reject_unmanaged()?;
+        #[cfg(feature = "runtime")]
+        self.http_config.check_runtime()?;
+        Ok(self.serve(incoming))
+    }
+
+    /// Construct a hyper server by an incoming, regardless of the lifecycle.
+    fn serve<I, IO>(self, incoming: I) -> Server<I, Self, Executor>
+    where
+        S: State + Send + Sync,
+        E: Send + Sync,
+        IO: 'static + Send + Sync + Unpin + AsyncRead + AsyncWrite,
+        I: Accept<Conn = AddrStream<IO>>,
+        I::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.http_config
+            .apply(Server::builder(incoming))
             .executor(self.exec.clone())
             .serve(self)
     }
 
-    /// Make a fake http service for test.
-    #[cfg(test)]
+    /// Construct a hyper server by an incoming, with graceful shutdown.
+    ///
+    /// Once `signal` resolves, the server stops accepting new connections,
+    /// then waits for in-flight requests and tasks spawned by `Executor` (like websocket tasks)
+    /// to complete. The returned future resolves when draining is finished
+    /// or `drain_timeout` elapses, whichever comes first.
+    /// Tasks still running after the timeout are detached, not aborted.
+    ///
+    /// The server fails if any timer option of `HttpConfig` is set out of a tokio runtime.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// use roa_core::{App, Context, Result};
+    /// use std::time::Duration;
+    /// # use roa_core::{AddrStream, Accept};
+    /// # use tokio::net::TcpStream;
+    ///
+    /// async fn end(_ctx: &mut Context) -> Result {
+    ///     Ok(())
+    /// }
+    ///
+    /// # async fn serve(incoming: impl 'static + Send + Accept<Conn = AddrStream<TcpStream>, Error = std::io::Error>) -> std::io::Result<()> {
+    /// let (trigger, signal) = futures::channel::oneshot::channel::<()>();
+    /// // shutdown when trigger is fired or dropped
+    /// let signal = async move {
+    ///     let _ = signal.await;
+    /// };
+    /// App::new()
+    ///     .end(end)
+    ///     .accept_graceful(incoming, signal, Duration::from_secs(30))
+    ///     .await
+    /// # }
+    /// ```
+    pub fn accept_graceful<I, IO>(
+        mut self,
+        incoming: I,
+        signal: impl 'static + Send + Future<Output = ()>,
+        drain_timeout: Duration,
+    ) -> Graceful
+    where
+        S: State + Send + Sync,
+        E: Send + Sync,
+        IO: 'static + Send + Sync + Unpin + AsyncRead + AsyncWrite,
+        I: 'static + Send + Accept<Conn = AddrStream<IO>>,
+        I::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        #[cfg(feature = "runtime")]
+        if let Err(err) = self.http_config.check_runtime() {
+            return Graceful::failed(err);
+        }
+        let exec = self.exec.clone();
+        let state = self.state.clone();
+        let lifecycle = std::mem::take(&mut self.lifecycle);
+        let shutdown = Shutdown::default();
+        let (sender, receiver) = oneshot::channel();
+        let server = self.serve(incoming).with_graceful_shutdown({
+            let shutdown = shutdown.clone();
+            async move {
+                signal.await;
+                shutdown.fire();
+                let _ = sender.send(());
+            }
+        });
+        Graceful::new(
+            server,
+            receiver,
+            shutdown,
+            exec,
+            drain_timeout,
+            lifecycle,
+            state,
+        )
+    }
+
+    /// Make an http service to serve requests in-process, without any connection.
+    /// It's useful for test.
     pub fn http_service(&self) -> HttpService<S, E>
     where
         S: Clone,
@@ -172,6 +412,7 @@ where
         let state = self.state.clone();
         let exec = self.exec.clone();
         HttpService::new(endpoint, addr.into(), exec, state)
+            .with_options(self.error_handler.clone(), self.body_limit)
     }
 }
 
@@ -192,8 +433,8 @@ type AppFuture<S, E> =
 
 impl<S, E, IO> Service<&AddrStream<IO>> for App<S, Arc<E>>
 where
-    S: State,
-    E: for<'a> Endpoint<'a, S>,
+    S: State + Send + Sync,
+    E: Send + Sync + for<'a> Endpoint<'a, S>,
     IO: 'static + Send + Sync + Unpin + AsyncRead + AsyncWrite,
 {
     type Response = HttpService<S, E>;
@@ -205,21 +446,28 @@ where
     fn call(&mut self, stream: &AddrStream<IO>) -> Self::Future {
         let endpoint = self.service.clone();
         let addr = stream.remote_addr;
+        let info = stream.info.clone();
         let state = self.state.clone();
         let exec = self.exec.clone();
-        Box::pin(async move { Ok(HttpService::new(endpoint, addr, exec, state)) })
+        let error_handler = self.error_handler.clone();
+        let body_limit = self.body_limit;
+        Box::pin(async move {
+            Ok(HttpService::new(endpoint, addr, exec, state)
+                .with_conn_info(info)
+                .with_options(error_handler, body_limit))
+        })
     }
 }
 
 type HttpFuture =
-    Pin<Box<dyn 'static + Future<Output = Result<HttpResponse<HyperBody>, Infallible>> + Send>>;
+    Pin<Box<dyn 'static + Future<Output = Result<HttpResponse<Body>, Infallible>> + Send>>;
 
 impl<S, E> Service<HttpRequest<HyperBody>> for HttpService<S, E>
 where
-    S: State,
-    E: for<'a> Endpoint<'a, S>,
+    S: State + Send + Sync,
+    E: Send + Sync + for<'a> Endpoint<'a, S>,
 {
-    type Response = HttpResponse<HyperBody>;
+    type Response = HttpResponse<Body>;
     type Error = Infallible;
     type Future = HttpFuture;
     impl_poll_ready!();
@@ -235,18 +483,48 @@ where
 }
 
 impl<S, E> HttpService<S, E> {
+    /// Construct an http service.
     pub fn new(endpoint: Arc<E>, remote_addr: SocketAddr, exec: Executor, state: S) -> Self {
         Self {
             endpoint,
             remote_addr,
+            conn: ConnInfo::default(),
             exec,
+            error_handler: None,
+            body_limit: None,
             state,
         }
     }
 
+    /// Set options of app.
+    #[inline]
+    fn with_options(
+        mut self,
+        error_handler: Option<SharedErrorHandler<S>>,
+        body_limit: Option<u64>,
+    ) -> Self {
+        self.error_handler = error_handler;
+        self.body_limit = body_limit;
+        self
+    }
+
+    /// Set the remote address of the client.
+    #[inline]
+    pub fn with_remote_addr(mut self, remote_addr: SocketAddr) -> Self {
+        self.remote_addr = remote_addr;
+        self
+    }
+
+    /// Set the metadata of the connection.
+    #[inline]
+    pub fn with_conn_info(mut self, info: ConnInfo) -> Self {
+        self.conn = info;
+        self
+    }
+
     /// Receive a request then return a response.
     /// The entry point of http service.
-    pub async fn serve(self, req: Request) -> Response
+    pub async fn serve(self, mut req: Request) -> Response
     where
         S: 'static,
         E: for<'a> Endpoint<'a, S>,
@@ -254,20 +532,32 @@ impl<S, E> HttpService<S, E> {
         let Self {
             endpoint,
             remote_addr,
+            conn,
             exec,
+            error_handler,
+            body_limit,
             state,
         } = self;
-        let mut ctx = Context::new(req, state, exec, remote_addr);
-        if let Err(status) = endpoint.call(&mut ctx).await {
-            ctx.resp.status = status.status_code;
-            if status.expose {
-                ctx.resp.write(status.message);
-            } else {
-                ctx.exec
-                    .spawn_blocking(move || tracing::error!("Uncaught status: {}", status))
-                    .await;
+        if body_limit.is_some() {
+            req.set_body_limit(body_limit);
+        }
+        let mut ctx = Context::new(req, state, exec, remote_addr, conn);
+        // cancel the token if this future is dropped before completion
+        let guard = ctx.cancellation_token().clone().drop_guard();
+        if let Err(mut status) = endpoint.call(&mut ctx).await {
+            if status.status_code != StatusCode::PAYLOAD_TOO_LARGE {
+                // the `PayloadTooLarge` may be wrapped by other errors
+                if let Some(too_large) = status.payload_too_large() {
+                    status = Status::from_payload_too_large(too_large);
+                }
+            }
+            ctx.resp.headers.extend(*status.headers.clone());
+            match error_handler {
+                Some(handler) => handler.handle(&mut ctx, status).await,
+                None => default_error_handler(&mut ctx, status).await,
             }
         }
+        guard.disarm();
         ctx.resp
     }
 }
@@ -278,7 +568,10 @@ impl<S: Clone, E> Clone for HttpService<S, E> {
             endpoint: self.endpoint.clone(),
             state: self.state.clone(),
             exec: self.exec.clone(),
+            error_handler: self.error_handler.clone(),
+            body_limit: self.body_limit,
             remote_addr: self.remote_addr,
+            conn: self.conn.clone(),
         }
     }
 }
@@ -287,7 +580,40 @@ impl<S: Clone, E> Clone for HttpService<S, E> {
 mod tests {
     use http::StatusCode;
 
-    use crate::{App, Request};
+    use crate::{status, App, Context, Request, Status};
+
+    #[tokio::test]
+    async fn cancel_on_drop() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::CancellationToken;
+
+        #[derive(Clone, Default)]
+        struct Token(Arc<Mutex<Option<CancellationToken>>>);
+
+        async fn end(ctx: &mut Context<Token>) -> crate::Result {
+            *ctx.0.lock().unwrap() = Some(ctx.cancellation_token().clone());
+            if ctx.uri().path() == "/pending" {
+                futures::future::pending::<()>().await;
+            }
+            Ok(())
+        }
+
+        let token = Token::default();
+        let service = App::state(token.clone()).end(end).http_service();
+        service.clone().serve(Request::default()).await;
+        let completed = token.0.lock().unwrap().take().unwrap();
+        assert!(!completed.is_cancelled());
+
+        let mut req = Request::default();
+        req.uri = "/pending".parse().unwrap();
+        let mut serving = Box::pin(service.serve(req));
+        assert!(futures::poll!(serving.as_mut()).is_pending());
+        let dropped = token.0.lock().unwrap().take().unwrap();
+        assert!(!dropped.is_cancelled());
+        drop(serving);
+        assert!(dropped.is_cancelled());
+    }
 
     #[tokio::test]
     async fn gate_simple() -> Result<(), Box<dyn std::error::Error>> {
@@ -296,4 +622,26 @@ mod tests {
         assert_eq!(StatusCode::OK, resp.status);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn on_error() -> Result<(), Box<dyn std::error::Error>> {
+        async fn handle_error(ctx: &mut Context, status: Status) {
+            ctx.resp.status = StatusCode::OK;
+            ctx.resp.write(format!("{}", status.status_code.as_u16()));
+        }
+        let service = App::new()
+            .on_error(handle_error)
+            .end(status!(StatusCode::IM_A_TEAPOT, "", false))
+            .http_service();
+        let mut resp = service.serve(Request::default()).await;
+        assert_eq!(StatusCode::OK, resp.status);
+        assert_eq!(
+            Some(b"418".as_ref()),
+            futures::StreamExt::next(&mut resp.body)
+                .await
+                .transpose()?
+                .as_deref()
+        );
+        Ok(())
+    }
 }
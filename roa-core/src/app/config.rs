@@ -0,0 +1,335 @@
+#[cfg(feature = "runtime")]
+use std::io;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+#[cfg(feature = "runtime")]
+use hyper::server::conn::Http;
+use hyper::server::Builder;
+
+/// The minimum buffer size of HTTP/1 connections allowed by hyper.
+const MIN_BUF_SIZE: usize = 8192;
+
+/// Configuration of the http server, applied to every connection.
+///
+/// Options not set fall back to the defaults of hyper.
+///
+/// ### Example
+/// ```rust
+/// use roa_core::{App, HttpConfig};
+///
+/// let config = HttpConfig::new()
+///     .http1_keepalive(false)
+///     .http1_max_buf_size(64 * 1024)
+///     .http2_max_concurrent_streams(128);
+/// let app = App::new().http_config(config).end(());
+/// ```
+///
+/// ### h2c
+/// HTTP/2 over cleartext with prior knowledge is served when `http2_only` is enabled.
+///
+/// ```rust
+/// use roa_core::{App, HttpConfig};
+///
+/// let app = App::new().http_config(HttpConfig::new().http2_only(true)).end(());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    http1_keepalive: Option<bool>,
+    http1_half_close: Option<bool>,
+    http1_max_buf_size: Option<usize>,
+    #[cfg(feature = "runtime")]
+    http1_header_read_timeout: Option<Duration>,
+    http1_only: bool,
+    http2_only: bool,
+    http2_initial_stream_window_size: Option<u32>,
+    http2_initial_connection_window_size: Option<u32>,
+    http2_adaptive_window: Option<bool>,
+    http2_max_frame_size: Option<u32>,
+    http2_max_concurrent_streams: Option<u32>,
+    #[cfg(feature = "runtime")]
+    http2_keep_alive_interval: Option<Duration>,
+    #[cfg(feature = "runtime")]
+    http2_keep_alive_timeout: Option<Duration>,
+}
+
+impl HttpConfig {
+    /// Construct a config with default options.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether HTTP/1 connections should support keep-alive.
+    ///
+    /// Default is `true`.
+    #[inline]
+    pub fn http1_keepalive(mut self, val: bool) -> Self {
+        self.http1_keepalive = Some(val);
+        self
+    }
+
+    /// Set whether HTTP/1 connections should support half-closures.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn http1_half_close(mut self, val: bool) -> Self {
+        self.http1_half_close = Some(val);
+        self
+    }
+
+    /// Set the maximum buffer size of HTTP/1 connections.
+    ///
+    /// Default is ~400kb.
+    ///
+    /// ### Panics
+    /// Panics if `val` is smaller than 8192.
+    #[inline]
+    pub fn http1_max_buf_size(mut self, val: usize) -> Self {
+        assert!(
+            val >= MIN_BUF_SIZE,
+            "the max buffer size must be at least {}",
+            MIN_BUF_SIZE
+        );
+        self.http1_max_buf_size = Some(val);
+        self
+    }
+
+    /// Set a timeout for reading request headers of HTTP/1 connections.
+    /// The connection is closed if a client does not transmit the entire header within it.
+    ///
+    /// The timer is driven by tokio, servers fail to start out of a tokio runtime if it's set.
+    ///
+    /// Default is `None`.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "runtime")))]
+    #[inline]
+    pub fn http1_header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.http1_header_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set whether to serve HTTP/1 only.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn http1_only(mut self, val: bool) -> Self {
+        self.http1_only = val;
+        self
+    }
+
+    /// Set whether to serve HTTP/2 only, which enables h2c with prior knowledge.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn http2_only(mut self, val: bool) -> Self {
+        self.http2_only = val;
+        self
+    }
+
+    /// Set the `SETTINGS_INITIAL_WINDOW_SIZE` option for HTTP/2 stream-level flow control.
+    ///
+    /// Default is 1 MiB.
+    #[inline]
+    pub fn http2_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http2_initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Set the max connection-level flow control for HTTP/2.
+    ///
+    /// Default is 1 MiB.
+    #[inline]
+    pub fn http2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http2_initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set whether to use an adaptive flow control of HTTP/2,
+    /// which overrides the window sizes set.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = Some(enabled);
+        self
+    }
+
+    /// Set the maximum frame size of HTTP/2.
+    ///
+    /// Default is 16,384.
+    #[inline]
+    pub fn http2_max_frame_size(mut self, size: u32) -> Self {
+        self.http2_max_frame_size = Some(size);
+        self
+    }
+
+    /// Set the `SETTINGS_MAX_CONCURRENT_STREAMS` option of HTTP/2 connections.
+    ///
+    /// Default is no limit.
+    #[inline]
+    pub fn http2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.http2_max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set an interval for HTTP/2 ping frames to keep connections alive.
+    ///
+    /// The timer is driven by tokio, servers fail to start out of a tokio runtime if it's set.
+    ///
+    /// Default is disabled.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "runtime")))]
+    #[inline]
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Set a timeout for receiving an acknowledgement of the HTTP/2 keep-alive ping.
+    /// The connection is closed if the ping is not acknowledged within it.
+    ///
+    /// It does nothing if `http2_keep_alive_interval` is not set.
+    /// The timer is driven by tokio, servers fail to start out of a tokio runtime if it's set.
+    ///
+    /// Default is 20 seconds.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "runtime")))]
+    #[inline]
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail if any option driven by tokio timers is set out of a tokio runtime,
+    /// hyper panics when serving with them, like on async-std.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn check_runtime(&self) -> io::Result<()> {
+        let timers = self.http1_header_read_timeout.is_some()
+            || self.http2_keep_alive_interval.is_some()
+            || self.http2_keep_alive_timeout.is_some();
+        if timers && tokio::runtime::Handle::try_current().is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "`http1_header_read_timeout` and `http2_keep_alive_*` need a tokio runtime",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply options to a hyper server builder.
+    pub(crate) fn apply<I, E>(&self, mut builder: Builder<I, E>) -> Builder<I, E> {
+        if let Some(val) = self.http1_keepalive {
+            builder = builder.http1_keepalive(val);
+        }
+        if let Some(val) = self.http1_half_close {
+            builder = builder.http1_half_close(val);
+        }
+        if let Some(val) = self.http1_max_buf_size {
+            builder = builder.http1_max_buf_size(val);
+        }
+        #[cfg(feature = "runtime")]
+        if let Some(timeout) = self.http1_header_read_timeout {
+            builder = builder.http1_header_read_timeout(timeout);
+        }
+        if self.http1_only {
+            builder = builder.http1_only(true);
+        }
+        if self.http2_only {
+            builder = builder.http2_only(true);
+        }
+        if let Some(size) = self.http2_initial_stream_window_size {
+            builder = builder.http2_initial_stream_window_size(size);
+        }
+        if let Some(size) = self.http2_initial_connection_window_size {
+            builder = builder.http2_initial_connection_window_size(size);
+        }
+        if let Some(enabled) = self.http2_adaptive_window {
+            builder = builder.http2_adaptive_window(enabled);
+        }
+        if let Some(size) = self.http2_max_frame_size {
+            builder = builder.http2_max_frame_size(size);
+        }
+        if let Some(max) = self.http2_max_concurrent_streams {
+            builder = builder.http2_max_concurrent_streams(max);
+        }
+        #[cfg(feature = "runtime")]
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder.http2_keep_alive_interval(interval);
+        }
+        #[cfg(feature = "runtime")]
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+        builder
+    }
+
+    /// Apply options to a hyper connection builder.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn apply_conn<E>(&self, http: &mut Http<E>) {
+        if let Some(val) = self.http1_keepalive {
+            http.http1_keep_alive(val);
+        }
+        if let Some(val) = self.http1_half_close {
+            http.http1_half_close(val);
+        }
+        if let Some(val) = self.http1_max_buf_size {
+            http.max_buf_size(val);
+        }
+        if let Some(timeout) = self.http1_header_read_timeout {
+            http.http1_header_read_timeout(timeout);
+        }
+        if self.http1_only {
+            http.http1_only(true);
+        }
+        if self.http2_only {
+            http.http2_only(true);
+        }
+        if let Some(size) = self.http2_initial_stream_window_size {
+            http.http2_initial_stream_window_size(size);
+        }
+        if let Some(size) = self.http2_initial_connection_window_size {
+            http.http2_initial_connection_window_size(size);
+        }
+        if let Some(enabled) = self.http2_adaptive_window {
+            http.http2_adaptive_window(enabled);
+        }
+        if let Some(size) = self.http2_max_frame_size {
+            http.http2_max_frame_size(size);
+        }
+        if let Some(max) = self.http2_max_concurrent_streams {
+            http.http2_max_concurrent_streams(max);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            http.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            http.http2_keep_alive_timeout(timeout);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::HttpConfig;
+
+    #[test]
+    fn timers_need_tokio() {
+        assert!(HttpConfig::new().check_runtime().is_ok());
+        let config = HttpConfig::new().http2_keep_alive_interval(Duration::from_secs(10));
+        let err = config.check_runtime().unwrap_err();
+        assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async move { assert!(config.check_runtime().is_ok()) });
+    }
+
+    #[test]
+    #[should_panic(expected = "the max buffer size must be at least 8192")]
+    fn small_buf_size() {
+        HttpConfig::new().http1_max_buf_size(4096);
+    }
+}
@@ -0,0 +1,83 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use futures::future::{pending, select, Either};
+use futures::task::{Context, Poll};
+use futures_timer::Delay;
+
+use super::lifecycle::{Lifecycle, Shutdown};
+use crate::{Executor, State};
+
+/// A server with graceful shutdown, returned by `App::accept_graceful`.
+///
+/// It runs start hooks before serving, and shutdown hooks after draining.
+/// It resolves after the shutdown signal is received and all in-flight requests
+/// and tasks spawned by `Executor` are complete, or the drain timeout elapses.
+pub struct Graceful(Pin<Box<dyn 'static + Send + Future<Output = io::Result<()>>>>);
+
+impl Graceful {
+    /// Construct a graceful server.
+    /// The `signal` receiver is notified once the shutdown signal is received.
+    pub(crate) fn new<S: State + Send + Sync>(
+        server: impl 'static + Send + Future<Output = hyper::Result<()>>,
+        signal: oneshot::Receiver<()>,
+        shutdown: Shutdown,
+        exec: Executor,
+        drain_timeout: Duration,
+        lifecycle: Lifecycle<S>,
+        state: S,
+    ) -> Self {
+        Self(Box::pin(async move {
+            let handles = lifecycle.start(&state, &exec, &shutdown).await?;
+            let drain_exec = exec.clone();
+            let drain = async move {
+                server.await.map_err(io::Error::other)?;
+                drain_exec.idle().await;
+                Ok(())
+            };
+            let deadline = async move {
+                match signal.await {
+                    Ok(()) => Delay::new(drain_timeout).await,
+                    // server stopped before receiving signal
+                    Err(_) => pending().await,
+                }
+            };
+            let result = match select(Box::pin(drain), Box::pin(deadline)).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => {
+                    tracing::warn!(
+                        "graceful shutdown timed out after {:?}, remaining tasks are detached",
+                        drain_timeout
+                    );
+                    Ok(())
+                }
+            };
+            // cancel background tasks still running
+            shutdown.fire();
+            for handle in handles {
+                handle.abort();
+            }
+            lifecycle.stop(&state, &exec).await;
+            result
+        }))
+    }
+}
+
+impl Graceful {
+    /// Construct a graceful server failing at once.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn failed(err: io::Error) -> Self {
+        Self(Box::pin(async move { Err(err) }))
+    }
+}
+
+impl Future for Graceful {
+    type Output = io::Result<()>;
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
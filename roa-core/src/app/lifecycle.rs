@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::io;
+
+use futures::future::{BoxFuture, FutureExt};
+
+use crate::{CancellationToken, Executor, JoinHandle, Result};
+
+/// A hook called with state and executor.
+type Hook<S> = Box<dyn 'static + Send + Sync + Fn(S, Executor) -> BoxFuture<'static, Result>>;
+
+/// A background task spawned with state and a shutdown signal.
+type Task<S> = Box<dyn 'static + Send + Sync + Fn(S, Shutdown) -> BoxFuture<'static, Result>>;
+
+/// A signal of server shutdown, passed to background tasks.
+///
+/// ### Example
+/// ```rust
+/// use roa_core::{App, Result, Shutdown};
+///
+/// async fn refresh(_state: (), shutdown: Shutdown) -> Result {
+///     while !shutdown.is_shutdown() {
+///         // refresh caches periodically
+/// #       shutdown.wait().await;
+///     }
+///     Ok(())
+/// }
+///
+/// let app = App::new().background(refresh).end(());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown(CancellationToken);
+
+impl Shutdown {
+    /// Fire the signal and wake all waiters.
+    #[inline]
+    pub(crate) fn fire(&self) {
+        self.0.cancel()
+    }
+
+    /// Whether the server is shutting down.
+    #[inline]
+    pub fn is_shutdown(&self) -> bool {
+        self.0.is_cancelled()
+    }
+
+    /// Wait until the server is shutting down.
+    #[inline]
+    pub async fn wait(&self) {
+        self.0.cancelled().await
+    }
+}
+
+/// Lifecycle hooks and background tasks of an app.
+pub(crate) struct Lifecycle<S> {
+    on_start: Vec<Hook<S>>,
+    on_shutdown: Vec<Hook<S>>,
+    background: Vec<Task<S>>,
+}
+
+impl<S> Default for Lifecycle<S> {
+    fn default() -> Self {
+        Self {
+            on_start: Vec::new(),
+            on_shutdown: Vec::new(),
+            background: Vec::new(),
+        }
+    }
+}
+
+impl<S> Lifecycle<S> {
+    /// Whether there is no hook or background task.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.on_start.is_empty() && self.on_shutdown.is_empty() && self.background.is_empty()
+    }
+
+    /// Fail if there is any hook or background task,
+    /// a server without graceful shutdown cannot run them.
+    pub(crate) fn reject_unmanaged(&self) -> io::Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "lifecycle hooks and background tasks need graceful shutdown, \
+                 serve the app by `accept_graceful` or `bind_graceful`",
+            ))
+        }
+    }
+
+    pub(crate) fn on_start<F, Fut>(&mut self, hook: F)
+    where
+        F: 'static + Send + Sync + Fn(S, Executor) -> Fut,
+        Fut: 'static + Send + Future<Output = Result>,
+    {
+        self.on_start
+            .push(Box::new(move |state, exec| hook(state, exec).boxed()))
+    }
+
+    pub(crate) fn on_shutdown<F, Fut>(&mut self, hook: F)
+    where
+        F: 'static + Send + Sync + Fn(S, Executor) -> Fut,
+        Fut: 'static + Send + Future<Output = Result>,
+    {
+        self.on_shutdown
+            .push(Box::new(move |state, exec| hook(state, exec).boxed()))
+    }
+
+    pub(crate) fn background<F, Fut>(&mut self, task: F)
+    where
+        F: 'static + Send + Sync + Fn(S, Shutdown) -> Fut,
+        Fut: 'static + Send + Future<Output = Result>,
+    {
+        self.background.push(Box::new(move |state, shutdown| {
+            task(state, shutdown).boxed()
+        }))
+    }
+}
+
+impl<S: Clone> Lifecycle<S> {
+    /// Run start hooks in order, then spawn background tasks.
+    /// Fail if any start hook fails.
+    pub(crate) async fn start(
+        &self,
+        state: &S,
+        exec: &Executor,
+        shutdown: &Shutdown,
+    ) -> io::Result<Vec<JoinHandle<()>>> {
+        for hook in self.on_start.iter() {
+            hook(state.clone(), exec.clone())
+                .await
+                .map_err(|status| io::Error::other(format!("fail to start: {}", status)))?;
+        }
+        let mut handles = Vec::with_capacity(self.background.len());
+        for task in self.background.iter() {
+            let task = task(state.clone(), shutdown.clone());
+            handles.push(exec.spawn(async move {
+                if let Err(status) = task.await {
+                    tracing::error!("background task failed: {}", status);
+                }
+            }));
+        }
+        Ok(handles)
+    }
+
+    /// Run shutdown hooks in order, failures are logged.
+    pub(crate) async fn stop(&self, state: &S, exec: &Executor) {
+        for hook in self.on_shutdown.iter() {
+            if let Err(status) = hook(state.clone(), exec.clone()).await {
+                tracing::error!("shutdown hook failed: {}", status);
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+use futures::future::poll_fn;
+use http::{Request as HttpRequest, Response as HttpResponse};
+use hyper::server::conn::Http;
+use hyper::service::Service;
+use hyper::Body as HyperBody;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::executor::{BlockingObj, FutureObj, LocalExecutor, LocalFutureObj};
+use crate::{Accept, AddrStream, App, Body, Endpoint, Executor, HttpService, Spawn, State};
+
+/// A runtime spawning futures on the current `LocalSet`.
+struct LocalExec;
+
+impl Spawn for LocalExec {
+    #[inline]
+    fn spawn(&self, fut: FutureObj) {
+        tokio::task::spawn_local(fut);
+    }
+
+    #[inline]
+    fn spawn_blocking(&self, task: BlockingObj) {
+        tokio::task::spawn_blocking(task);
+    }
+
+    #[inline]
+    fn spawn_local(&self, fut: LocalFutureObj) {
+        tokio::task::spawn_local(fut);
+    }
+
+    #[inline]
+    fn sleep(&self, duration: Duration) -> FutureObj {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// An http service serving on the current thread, its future can be `!Send`.
+struct LocalService<S, E>(HttpService<S, E>);
+
+impl<S, E> App<S, Arc<E>>
+where
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    /// Serve connections of an incoming on the current thread.
+    ///
+    /// Neither the state, middlewares nor their futures are required to be `Send` or `Sync`,
+    /// so they can hold `Rc`, `RefCell` or thread-local caches.
+    /// The executor of app is replaced by one spawning tasks on the current `LocalSet`,
+    /// `Executor::spawn_local` is supported.
+    ///
+    /// It must be called within a `tokio::task::LocalSet`, usually one per thread
+    /// with its own listener, like `roa::tcp::ThreadPerCore`.
+    /// Fail if any lifecycle hook or background task is registered,
+    /// they only run with graceful shutdown.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// use roa_core::{App, Context, Result};
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// # use roa_core::{AddrStream, Accept};
+    /// # use tokio::net::TcpStream;
+    ///
+    /// async fn end(ctx: &mut Context<Rc<RefCell<u64>>>) -> Result {
+    ///     *ctx.borrow_mut() += 1;
+    ///     Ok(())
+    /// }
+    ///
+    /// # async fn serve(incoming: impl Unpin + Accept<Conn = AddrStream<TcpStream>, Error = std::io::Error>) -> std::io::Result<()> {
+    /// tokio::task::LocalSet::new()
+    ///     .run_until(App::state(Rc::default()).end(end).serve_local(incoming))
+    ///     .await
+    /// # }
+    /// ```
+    pub async fn serve_local<I, IO>(mut self, mut incoming: I) -> io::Result<()>
+    where
+        IO: 'static + Send + Unpin + AsyncRead + AsyncWrite,
+        I: Unpin + Accept<Conn = AddrStream<IO>>,
+        I::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.lifecycle.reject_unmanaged()?;
+        self.exec = Executor::new(LocalExec);
+        let mut http = Http::new().with_executor(LocalExecutor(self.exec.clone()));
+        self.http_config.apply_conn(&mut http);
+        while let Some(stream) = poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx)).await {
+            let stream = stream.map_err(io::Error::other)?;
+            let service = self
+                .http_service()
+                .with_remote_addr(stream.remote_addr)
+                .with_conn_info(stream.info.clone());
+            let conn = http
+                .serve_connection(stream, LocalService(service))
+                .with_upgrades();
+            drop(self.exec.spawn_local(async move {
+                if let Err(err) = conn.await {
+                    tracing::debug!("error serving connection: {}", err);
+                }
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl<S, E> Service<HttpRequest<HyperBody>> for LocalService<S, E>
+where
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    type Response = HttpResponse<Body>;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn 'static + Future<Output = Result<HttpResponse<Body>, Infallible>>>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    #[inline]
+    fn call(&mut self, req: HttpRequest<HyperBody>) -> Self::Future {
+        let service = self.0.clone();
+        Box::pin(async move { Ok(service.serve(req.into()).await.into()) })
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::executor::{BlockingObj, FutureObj};
 use crate::{App, Spawn};
 
@@ -38,4 +40,9 @@ impl Spawn for Exec {
     fn spawn_blocking(&self, task: BlockingObj) {
         tokio::task::spawn_blocking(task);
     }
+
+    #[inline]
+    fn sleep(&self, duration: Duration) -> FutureObj {
+        Box::pin(tokio::time::sleep(duration))
+    }
 }
@@ -8,11 +8,16 @@ use futures::ready;
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tracing::{instrument, trace};
 
+use crate::ConnInfo;
+
 /// A transport returned yieled by `AddrIncoming`.
 pub struct AddrStream<IO> {
     /// The remote address of this stream.
     pub remote_addr: SocketAddr,
 
+    /// Metadata of this connection.
+    pub info: ConnInfo,
+
     /// The inner stream.
     pub stream: IO,
 }
@@ -23,9 +28,17 @@ impl<IO> AddrStream<IO> {
     pub fn new(remote_addr: SocketAddr, stream: IO) -> AddrStream<IO> {
         AddrStream {
             remote_addr,
+            info: ConnInfo::default(),
             stream,
         }
     }
+
+    /// Set the metadata of this connection.
+    #[inline]
+    pub fn with_info(mut self, info: ConnInfo) -> AddrStream<IO> {
+        self.info = info;
+        self
+    }
 }
 
 impl<IO> AsyncRead for AddrStream<IO>
@@ -78,6 +91,7 @@ impl<IO> Debug for AddrStream<IO> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AddrStream")
             .field("remote_addr", &self.remote_addr)
+            .field("info", &self.info)
             .finish()
     }
 }
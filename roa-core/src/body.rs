@@ -1,10 +1,13 @@
+use std::future::Future;
 use std::mem;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use bytes::{Bytes, BytesMut};
-use futures::future::ok;
-use futures::stream::{once, Stream, StreamExt};
+use futures::future::{ok, ready};
+use futures::stream::{empty, once, Stream, StreamExt};
+use http::HeaderMap;
+use hyper::body::{HttpBody, SizeHint};
 use tokio::io::{self, AsyncRead, ReadBuf};
 
 const DEFAULT_CHUNK_SIZE: usize = 4096;
@@ -33,6 +36,24 @@ const DEFAULT_CHUNK_SIZE: usize = 4096;
 ///     })
 /// }
 /// ```
+///
+/// ### Length and trailers
+///
+/// The exact length is tracked when composed of bytes and sized readers,
+/// then Content-Length will be set automatically.
+///
+/// ```rust
+/// use roa_core::Body;
+/// use roa_core::http::HeaderMap;
+///
+/// let mut body = Body::empty();
+/// body.write("Hello, ").write_sized_reader(&b"World"[..], 5);
+/// assert_eq!(Some(12), body.exact_len());
+///
+/// let mut trailers = HeaderMap::new();
+/// trailers.insert("grpc-status", "0".parse().unwrap());
+/// body.set_trailers(trailers);
+/// ```
 pub enum Body {
     /// Empty kind
     Empty,
@@ -45,8 +66,17 @@ pub enum Body {
 }
 
 /// A boxed stream.
-#[derive(Default)]
-pub struct Segment(Option<Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Sync + Send + 'static>>>);
+type BoxStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Sync + Send + 'static>>;
+
+/// A boxed future resolving trailers.
+type BoxTrailers = Pin<Box<dyn Future<Output = io::Result<HeaderMap>> + Sync + Send + 'static>>;
+
+/// A boxed stream with its remaining length and trailers.
+pub struct Segment {
+    stream: Option<BoxStream>,
+    len: Option<u64>,
+    trailers: Option<BoxTrailers>,
+}
 
 impl Body {
     /// Construct an empty body.
@@ -67,7 +97,7 @@ impl Body {
     where
         S: Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
     {
-        Body::Stream(Segment::new(stream))
+        Body::Stream(Segment::new(stream, None))
     }
 
     /// Write stream.
@@ -75,17 +105,40 @@ impl Body {
     pub fn write_stream(
         &mut self,
         stream: impl Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
+    ) -> &mut Self {
+        self.write_segment(stream, None)
+    }
+
+    /// Write stream with its exact length, if known.
+    fn write_segment(
+        &mut self,
+        stream: impl Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
+        len: Option<u64>,
     ) -> &mut Self {
         match self {
             Body::Empty => {
-                *self = Self::stream(stream);
+                *self = Body::Stream(Segment::new(stream, len));
             }
             Body::Once(bytes) => {
+                let prefix_len = bytes.len() as u64;
                 let stream = once(ok(mem::take(bytes))).chain(stream);
-                *self = Self::stream(stream);
+                *self = Body::Stream(Segment::new(stream, len.map(|len| len + prefix_len)));
             }
             Body::Stream(segment) => {
-                *self = Self::stream(mem::take(segment).chain(stream));
+                let Segment {
+                    stream: prefix,
+                    len: prefix_len,
+                    trailers,
+                } = mem::take(segment);
+                let len = prefix_len
+                    .zip(len)
+                    .map(|(prefix_len, len)| prefix_len + len);
+                let mut composed = match prefix {
+                    Some(prefix) => Segment::new(prefix.chain(stream), len),
+                    None => Segment::new(stream, len),
+                };
+                composed.trailers = trailers;
+                *segment = composed;
             }
         }
         self
@@ -110,6 +163,20 @@ impl Body {
         self.write_stream(ReaderStream::new(reader, chunk_size))
     }
 
+    /// Write reader with its exact size in bytes,
+    /// then Content-Length can be set automatically.
+    ///
+    /// The response fails if the reader yields a different size.
+    #[inline]
+    pub fn write_sized_reader(
+        &mut self,
+        reader: impl AsyncRead + Sync + Send + Unpin + 'static,
+        size: u64,
+    ) -> &mut Self {
+        let stream = SizedStream::new(ReaderStream::new(reader, DEFAULT_CHUNK_SIZE), size);
+        self.write_segment(stream, Some(size))
+    }
+
     /// Write `Bytes`.
     #[inline]
     pub fn write(&mut self, data: impl Into<Bytes>) -> &mut Self {
@@ -118,15 +185,125 @@ impl Body {
                 *self = Self::once(data.into());
                 self
             }
-            body => body.write_stream(once(ok(data.into()))),
+            body => {
+                let data = data.into();
+                let len = data.len() as u64;
+                body.write_segment(once(ok(data)), Some(len))
+            }
+        }
+    }
+
+    /// The exact length of remaining body in bytes, `None` if it's unknown.
+    #[inline]
+    pub fn exact_len(&self) -> Option<u64> {
+        match self {
+            Body::Empty => Some(0),
+            Body::Once(bytes) => Some(bytes.len() as u64),
+            Body::Stream(segment) => segment.len,
+        }
+    }
+
+    /// Map the body into a new stream, keeping its exact length and trailers.
+    ///
+    /// The mapping must not change the size of body, like a stream to log or count bytes.
+    /// The body passed to the mapping has no trailers, they are sent after the new stream.
+    pub fn map_stream<S>(&mut self, f: impl FnOnce(Body) -> S) -> &mut Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
+    {
+        let len = self.exact_len();
+        self.encode_stream(f);
+        if let Body::Stream(segment) = self {
+            segment.len = len;
+        }
+        self
+    }
+
+    /// Encode the body into a new stream, keeping its trailers.
+    ///
+    /// The exact length becomes unknown, it's useful for content encodings like compression.
+    /// The body passed to the encoding has no trailers, they are sent after the new stream.
+    pub fn encode_stream<S>(&mut self, f: impl FnOnce(Body) -> S) -> &mut Self
+    where
+        S: Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
+    {
+        let trailers = match self {
+            Body::Stream(segment) => segment.trailers.take(),
+            _ => None,
+        };
+        let mut segment = Segment::new(f(mem::take(self)), None);
+        segment.trailers = trailers;
+        *self = Body::Stream(segment);
+        self
+    }
+
+    /// Set trailers sent after the body.
+    ///
+    /// Trailers are only sent over HTTP/2, they are dropped by HTTP/1.1 connections.
+    #[inline]
+    pub fn set_trailers(&mut self, trailers: HeaderMap) -> &mut Self {
+        self.defer_trailers(ready(Ok(trailers)))
+    }
+
+    /// Set a future resolving trailers, which is polled after the body is drained.
+    /// It's useful to send checksum after stream.
+    pub fn defer_trailers(
+        &mut self,
+        trailers: impl Future<Output = io::Result<HeaderMap>> + Sync + Send + 'static,
+    ) -> &mut Self {
+        if !matches!(self, Body::Stream(_)) {
+            self.write_segment(empty(), Some(0));
+        }
+        if let Body::Stream(segment) = self {
+            segment.trailers = Some(Box::pin(trailers));
+        }
+        self
+    }
+
+    /// Poll trailers, it should be called after the body is drained.
+    #[inline]
+    pub fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<Option<HeaderMap>>> {
+        match &mut *self {
+            Body::Stream(Segment {
+                trailers: Some(trailers),
+                ..
+            }) => {
+                let trailers = futures::ready!(trailers.as_mut().poll(cx));
+                if let Body::Stream(segment) = &mut *self {
+                    segment.trailers = None;
+                }
+                Poll::Ready(trailers.map(Some))
+            }
+            _ => Poll::Ready(Ok(None)),
         }
     }
 }
 
 impl Segment {
     #[inline]
-    fn new(stream: impl Stream<Item = io::Result<Bytes>> + Sync + Send + 'static) -> Self {
-        Self(Some(Box::pin(stream)))
+    fn new(
+        stream: impl Stream<Item = io::Result<Bytes>> + Sync + Send + 'static,
+        len: Option<u64>,
+    ) -> Self {
+        Self {
+            stream: Some(Box::pin(stream)),
+            len,
+            trailers: None,
+        }
+    }
+}
+
+impl Default for Segment {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            stream: None,
+            len: Some(0),
+            trailers: None,
+        }
     }
 }
 
@@ -141,6 +318,40 @@ impl From<Body> for hyper::Body {
     }
 }
 
+impl HttpBody for Body {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    #[inline]
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        self.poll_next(cx)
+    }
+
+    #[inline]
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Body::poll_trailers(self, cx)
+    }
+
+    #[inline]
+    fn is_end_stream(&self) -> bool {
+        matches!(self, Body::Empty)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> SizeHint {
+        match self.exact_len() {
+            Some(len) => SizeHint::with_exact(len),
+            None => SizeHint::default(),
+        }
+    }
+}
+
 impl Default for Body {
     #[inline]
     fn default() -> Self {
@@ -160,6 +371,57 @@ impl<R> ReaderStream<R> {
     }
 }
 
+/// A stream which fails if it doesn't yield exactly the size in bytes.
+struct SizedStream<S> {
+    stream: S,
+    size: u64,
+    /// The remaining size, `None` if the stream failed.
+    remaining: Option<u64>,
+}
+
+impl<S> SizedStream<S> {
+    #[inline]
+    fn new(stream: S, size: u64) -> Self {
+        Self {
+            stream,
+            size,
+            remaining: Some(size),
+        }
+    }
+
+    #[inline]
+    fn fail(&mut self, kind: io::ErrorKind, yielded: &str) -> Poll<Option<io::Result<Bytes>>> {
+        self.remaining = None;
+        let message = format!("sized reader yields {} than {} bytes", yielded, self.size);
+        Poll::Ready(Some(Err(io::Error::new(kind, message))))
+    }
+}
+
+impl<S> Stream for SizedStream<S>
+where
+    S: Stream<Item = io::Result<Bytes>> + Unpin,
+{
+    type Item = io::Result<Bytes>;
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let remaining = match self.remaining {
+            None => return Poll::Ready(None),
+            Some(remaining) => remaining,
+        };
+        match futures::ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+            Some(Ok(data)) if data.len() as u64 > remaining => {
+                self.fail(io::ErrorKind::InvalidData, "more")
+            }
+            Some(Ok(data)) => {
+                self.remaining = Some(remaining - data.len() as u64);
+                Poll::Ready(Some(Ok(data)))
+            }
+            None if remaining > 0 => self.fail(io::ErrorKind::UnexpectedEof, "less"),
+            item => Poll::Ready(item),
+        }
+    }
+}
+
 impl<R> Stream for ReaderStream<R>
 where
     R: AsyncRead + Unpin,
@@ -201,10 +463,14 @@ impl Stream for Segment {
     type Item = io::Result<Bytes>;
     #[inline]
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match self.0 {
-            None => Poll::Ready(None),
-            Some(ref mut stream) => stream.as_mut().poll_next(cx),
+        let item = match self.stream {
+            None => return Poll::Ready(None),
+            Some(ref mut stream) => futures::ready!(stream.as_mut().poll_next(cx)),
+        };
+        if let (Some(Ok(data)), Some(len)) = (&item, &mut self.len) {
+            *len = len.saturating_sub(data.len() as u64);
         }
+        Poll::Ready(item)
     }
 }
 
@@ -212,7 +478,10 @@ impl Stream for Segment {
 mod tests {
     use std::io;
 
+    use bytes::Bytes;
     use futures::{AsyncReadExt, TryStreamExt};
+    use http::HeaderMap;
+    use hyper::body::HttpBody;
     use tokio::fs::File;
 
     use super::Body;
@@ -257,4 +526,90 @@ mod tests {
         assert_eq!("Hello, HexileeHexilee.", read_body(body).await?);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn exact_len() -> std::io::Result<()> {
+        let mut body = Body::empty();
+        assert_eq!(Some(0), body.exact_len());
+        body.write("He").write("llo, ");
+        assert_eq!(Some(7), body.exact_len());
+        body.write_sized_reader(File::open("../assets/author.txt").await?, 7);
+        assert_eq!(Some(14), body.size_hint().exact());
+        body.data().await.transpose()?;
+        assert_eq!(Some(12), body.exact_len());
+        body.write_reader(File::open("../assets/author.txt").await?);
+        assert_eq!(None, body.exact_len());
+        assert_eq!("llo, HexileeHexilee", read_body(body).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sized_reader_mismatch() {
+        for size in [4, 6] {
+            let mut body = Body::empty();
+            body.write_sized_reader(&b"Hello"[..], size);
+            assert_eq!(Some(size), body.exact_len());
+            assert!(read_body(body).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn map_stream() -> std::io::Result<()> {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+
+        let mut body = Body::once("Hello");
+        body.set_trailers(trailers.clone())
+            .map_stream(|body| body.map_ok(|data| data.to_ascii_uppercase().into()));
+        assert_eq!(Some(5), body.exact_len());
+        assert_eq!(Some(Bytes::from("HELLO")), body.data().await.transpose()?);
+        assert_eq!(None, body.data().await.transpose()?);
+        assert_eq!(Some(trailers), body.trailers().await?);
+
+        let mut body = Body::empty();
+        body.write_reader(File::open("../assets/author.txt").await?)
+            .map_stream(|body| body);
+        assert_eq!(None, body.exact_len());
+        assert_eq!(None, body.trailers().await?);
+        assert_eq!("Hexilee", read_body(body).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encode_stream() -> std::io::Result<()> {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+
+        let mut body = Body::once("Hello");
+        body.set_trailers(trailers.clone())
+            .encode_stream(|body| body.map_ok(|data| data.slice(1..)));
+        assert_eq!(None, body.exact_len());
+        assert_eq!(Some(Bytes::from("ello")), body.data().await.transpose()?);
+        assert_eq!(None, body.data().await.transpose()?);
+        assert_eq!(Some(trailers), body.trailers().await?);
+
+        let mut body = Body::once("Hello");
+        body.encode_stream(|body| body);
+        assert_eq!(None, body.trailers().await?);
+        assert_eq!("Hello", read_body(body).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn trailers() -> std::io::Result<()> {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+
+        let mut body = Body::once("Hello");
+        body.set_trailers(trailers.clone()).write(", World");
+        assert_eq!(Some(12), body.exact_len());
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk?);
+        }
+        assert_eq!(b"Hello, World", data.as_slice());
+        assert_eq!(Some(trailers), body.trailers().await?);
+        assert_eq!(None, body.trailers().await?);
+        Ok(())
+    }
 }
@@ -0,0 +1,32 @@
+/// A token to notify spawned works to stop early, re-exported from `tokio-util`.
+///
+/// The token of `Context` is cancelled when the client hangs up before response is ready,
+/// that is, the request future is dropped by server.
+///
+/// ### Example
+/// ```rust
+/// use roa_core::{App, Context, Result};
+///
+/// async fn end(ctx: &mut Context) -> Result {
+///     let token = ctx.cancellation_token().clone();
+///     let sum = ctx
+///         .exec
+///         .spawn_blocking(move || {
+///             let mut sum = 0u64;
+///             for i in 0..1_000_000 {
+///                 if token.is_cancelled() {
+///                     // nobody is waiting for the result
+///                     break;
+///                 }
+///                 sum += i;
+///             }
+///             sum
+///         })
+///         .await?;
+///     ctx.resp.write(sum.to_string());
+///     Ok(())
+/// }
+///
+/// let app = App::new().end(end);
+/// ```
+pub use tokio_util::sync::CancellationToken;
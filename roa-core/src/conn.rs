@@ -0,0 +1,81 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+
+/// Metadata of a connection, filled by incoming.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa_core::{App, Context, Result};
+///
+/// async fn end(ctx: &mut Context) -> Result {
+///     if let Some(tls) = ctx.conn.tls() {
+///         let alpn = tls.alpn_protocol.as_deref();
+///         ctx.resp.write(format!("tls, alpn: {:?}", alpn));
+///     }
+///     Ok(())
+/// }
+///
+/// let app = App::new().end(end);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConnInfo {
+    local_addr: Option<SocketAddr>,
+    tls: Arc<OnceLock<TlsInfo>>,
+}
+
+/// TLS metadata of a connection.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TlsInfo {
+    /// The negotiated ALPN protocol, like `b"h2"`.
+    pub alpn_protocol: Option<Vec<u8>>,
+
+    /// The SNI hostname sent by client.
+    pub server_name: Option<String>,
+
+    /// The certificate chain of client in DER, the end-entity certificate first.
+    /// It's empty if client authentication is not enabled.
+    pub peer_certificates: Vec<Vec<u8>>,
+}
+
+impl ConnInfo {
+    /// Construct an empty connection info.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the local address.
+    #[inline]
+    pub fn with_local_addr(mut self, addr: SocketAddr) -> Self {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// The local address of this connection.
+    #[inline]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Set TLS info once handshake is completed.
+    ///
+    /// The info is shared by all clones, and can only be set once;
+    /// the info is returned as an error if it's already set.
+    #[inline]
+    pub fn set_tls(&self, info: TlsInfo) -> Result<(), TlsInfo> {
+        self.tls.set(info)
+    }
+
+    /// TLS info of this connection, `None` if it's not a TLS connection.
+    #[inline]
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.get()
+    }
+
+    /// Whether this is a TLS connection.
+    #[inline]
+    pub fn is_tls(&self) -> bool {
+        self.tls().is_some()
+    }
+}
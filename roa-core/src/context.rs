@@ -1,3 +1,5 @@
+mod extensions;
+mod request_id;
 mod storage;
 
 use std::any::Any;
@@ -5,13 +7,16 @@ use std::borrow::Cow;
 use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+pub(crate) use extensions::Extensions;
 use http::header::AsHeaderName;
 use http::{Method, StatusCode, Uri, Version};
+pub use request_id::RequestId;
 pub use storage::Variable;
 use storage::{Storage, Value};
 
-use crate::{status, Executor, Request, Response};
+use crate::{status, CancellationToken, ConnInfo, Executor, Request, Response};
 
 /// A structure to share request, response and other data between middlewares.
 ///
@@ -46,21 +51,37 @@ pub struct Context<S = ()> {
     /// Socket addr of last client or proxy.
     pub remote_addr: SocketAddr,
 
+    /// Metadata of the connection, like local address and TLS info.
+    pub conn: ConnInfo,
+
     storage: Storage,
+    extensions: Extensions,
+    deadline: Option<Instant>,
+    cancellation: CancellationToken,
     state: S,
 }
 
 impl<S> Context<S> {
     /// Construct a context from a request, an app and a addr_stream.
     #[inline]
-    pub(crate) fn new(request: Request, state: S, exec: Executor, remote_addr: SocketAddr) -> Self {
+    pub(crate) fn new(
+        request: Request,
+        state: S,
+        exec: Executor,
+        remote_addr: SocketAddr,
+        conn: ConnInfo,
+    ) -> Self {
         Self {
             req: request,
             resp: Response::default(),
             state,
             exec,
             storage: Storage::default(),
+            extensions: Extensions::default(),
+            deadline: None,
+            cancellation: CancellationToken::default(),
             remote_addr,
+            conn,
         }
     }
 
@@ -308,6 +329,129 @@ impl<S> Context<S> {
     {
         self.load_scoped::<PublicScope, V>(key)
     }
+
+    /// Insert an extension keyed by its type, return the old one if exists.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, Result, Next};
+    ///
+    /// #[derive(Clone)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// async fn gate(ctx: &mut Context, next: Next<'_>) -> Result {
+    ///     ctx.insert_ext(User { name: "Hexilee".to_string() });
+    ///     next.await
+    /// }
+    ///
+    /// async fn end(ctx: &mut Context) -> Result {
+    ///     assert_eq!("Hexilee", ctx.ext::<User>().unwrap().name);
+    ///     Ok(())
+    /// }
+    ///
+    /// let app = App::new().gate(gate).end(end);
+    /// ```
+    #[inline]
+    pub fn insert_ext<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        self.extensions.insert(value)
+    }
+
+    /// Get a reference to the extension of type `T`.
+    #[inline]
+    pub fn ext<T: Any>(&self) -> Option<&T> {
+        self.extensions.get()
+    }
+
+    /// Get a mutable reference to the extension of type `T`, to update it in place.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, Result, Next};
+    ///
+    /// #[derive(Clone)]
+    /// struct Counter(usize);
+    ///
+    /// async fn gate(ctx: &mut Context, next: Next<'_>) -> Result {
+    ///     ctx.insert_ext(Counter(0));
+    ///     next.await?;
+    ///     assert_eq!(1, ctx.ext::<Counter>().unwrap().0);
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn end(ctx: &mut Context) -> Result {
+    ///     if let Some(counter) = ctx.ext_mut::<Counter>() {
+    ///         counter.0 += 1;
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let app = App::new().gate(gate).end(end);
+    /// ```
+    #[inline]
+    pub fn ext_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.extensions.get_mut()
+    }
+
+    /// Remove the extension of type `T` and return it.
+    #[inline]
+    pub fn remove_ext<T: Any>(&mut self) -> Option<T> {
+        self.extensions.remove()
+    }
+
+    /// The id of this request, set by a request id middleware.
+    #[inline]
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.ext()
+    }
+
+    /// The cancellation token of this request, cancelled when the request future is dropped,
+    /// like the client hangs up before response is ready.
+    ///
+    /// Clone it into spawned or blocking works to stop them early.
+    #[inline]
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// The deadline of this request, set by timeout middlewares.
+    #[inline]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Set or clear the deadline of this request, return the previous one.
+    #[inline]
+    pub fn set_deadline(&mut self, deadline: impl Into<Option<Instant>>) -> Option<Instant> {
+        std::mem::replace(&mut self.deadline, deadline.into())
+    }
+
+    /// Time left before the deadline, zero if it has passed.
+    /// It's useful to set timeout of outbound calls.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, Result};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let app = App::new().end(get);
+    ///
+    /// async fn get(ctx: &mut Context) -> Result {
+    ///     assert!(ctx.remaining().is_none());
+    ///     ctx.set_deadline(Instant::now() + Duration::from_secs(1));
+    ///     assert!(ctx.remaining().unwrap() <= Duration::from_secs(1));
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
 }
 
 /// Public storage scope.
@@ -337,7 +481,11 @@ impl<S: Clone> Clone for Context<S> {
             state: self.state.clone(),
             exec: self.exec.clone(),
             storage: self.storage.clone(),
+            extensions: self.extensions.clone(),
+            deadline: self.deadline,
+            cancellation: self.cancellation.clone(),
             remote_addr: self.remote_addr,
+            conn: self.conn.clone(),
         }
     }
 }
@@ -0,0 +1,115 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A value can be stored in `Extensions`.
+trait Extension: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn Extension>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T> Extension for T
+where
+    T: Any + Clone + Send + Sync,
+{
+    #[inline]
+    fn clone_box(&self) -> Box<dyn Extension> {
+        Box::new(self.clone())
+    }
+
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl Clone for Box<dyn Extension> {
+    #[inline]
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+/// A type map of context extensions.
+#[derive(Clone, Default)]
+pub struct Extensions(HashMap<TypeId, Box<dyn Extension>>);
+
+impl Extensions {
+    /// Insert a value, return the old one if exists.
+    #[inline]
+    pub fn insert<T>(&mut self, value: T) -> Option<T>
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        self.0
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(downcast)
+    }
+
+    /// Get a reference to the value of type `T`.
+    #[inline]
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any().downcast_ref())
+    }
+
+    /// Get a mutable reference to the value of type `T`.
+    #[inline]
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.0
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| (**value).as_any_mut().downcast_mut())
+    }
+
+    /// Remove the value of type `T`.
+    #[inline]
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.0.remove(&TypeId::of::<T>()).and_then(downcast)
+    }
+}
+
+#[inline]
+fn downcast<T: Any>(value: Box<dyn Extension>) -> Option<T> {
+    value.into_any().downcast().ok().map(|value| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Extensions;
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn type_keyed() {
+        let mut exts = Extensions::default();
+        assert!(exts.insert(1u64).is_none());
+        assert_eq!(Some(1), exts.insert(2u64));
+        assert!(exts.get::<u32>().is_none());
+        *exts.get_mut::<u64>().unwrap() += 1;
+        assert_eq!(Some(&3), exts.get::<u64>());
+
+        exts.insert(User {
+            name: "Hexilee".into(),
+        });
+        let cloned = exts.clone();
+        exts.get_mut::<User>().unwrap().name = "Alice".into();
+        assert_eq!("Hexilee", cloned.get::<User>().unwrap().name);
+        assert_eq!("Alice", exts.remove::<User>().unwrap().name);
+        assert!(exts.get::<User>().is_none());
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+
+/// The identifier of a request, used to correlate logs of the same request.
+///
+/// It's stored as an extension of context, usually by the `roa::request_id` middleware,
+/// and read by loggers and error handlers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(Arc<str>);
+
+impl RequestId {
+    /// Construct a request id.
+    #[inline]
+    pub fn new(id: impl Into<Arc<str>>) -> Self {
+        Self(id.into())
+    }
+
+    /// Get the id as str.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RequestId {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for RequestId {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
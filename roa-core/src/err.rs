@@ -1,7 +1,14 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::result::Result as StdResult;
+use std::sync::Arc;
 
+use http::header::IntoHeaderName;
 pub use http::StatusCode;
+use http::{HeaderMap, HeaderValue};
+
+use crate::{async_trait, Context, PayloadTooLarge, RequestId};
 
 /// Type alias for `StdResult`.
 pub type Result<R = ()> = StdResult<R, Status>;
@@ -11,6 +18,8 @@ pub type Result<R = ()> = StdResult<R, Status>;
 /// - `status!(status_code)` will be expanded to `status!(status_code, "")`
 /// - `status!(status_code, message)` will be expanded to `status!(status_code, message, true)`
 /// - `status!(status_code, message, expose)` will be expanded to `Status::new(status_code, message, expose)`
+/// - `status!(status_code, ...; method(args), ...)` will be expanded to `status!(status_code, ...).method(args)...`,
+///   to set headers, error code, source or details.
 ///
 /// ### Example
 /// ```rust
@@ -38,6 +47,9 @@ macro_rules! status {
     ($status_code:expr, $message:expr, $expose:expr) => {
         $crate::Status::new($status_code, $message, $expose)
     };
+    ($status_code:expr $(, $arg:expr)*; $($method:ident($($param:expr),* $(,)?)),+ $(,)?) => {
+        $crate::status!($status_code $(, $arg)*)$(.$method($($param),*))+
+    };
 }
 
 /// Throw an `Err(Status)`.
@@ -45,6 +57,7 @@ macro_rules! status {
 /// - `throw!(status_code)` will be expanded to `throw!(status_code, "")`
 /// - `throw!(status_code, message)` will be expanded to `throw!(status_code, message, true)`
 /// - `throw!(status_code, message, expose)` will be expanded to `return Err(Status::new(status_code, message, expose));`
+/// - `throw!(status_code, ...; method(args), ...)` will be expanded to `return Err(status!(status_code, ...; method(args), ...));`
 ///
 /// ### Example
 /// ```rust
@@ -75,10 +88,15 @@ macro_rules! throw {
     ($status_code:expr, $message:expr, $expose:expr) => {
         return core::result::Result::Err($crate::status!($status_code, $message, $expose))
     };
+    ($status_code:expr $(, $arg:expr)*; $($method:ident($($param:expr),* $(,)?)),+ $(,)?) => {
+        return core::result::Result::Err(
+            $crate::status!($status_code $(, $arg)*; $($method($($param),*)),+)
+        )
+    };
 }
 
 /// The `Status` of roa.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Status {
     /// StatusCode will be responded to client if Error is thrown by the top middleware.
     ///
@@ -119,6 +137,37 @@ pub struct Status {
 
     /// if message exposed.
     pub expose: bool,
+
+    /// Headers will be responded to client if Error is thrown by the top middleware,
+    /// like `Retry-After` or `WWW-Authenticate`.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, Result, throw};
+    /// use roa_core::http::StatusCode;
+    /// use roa_core::http::header::{HeaderValue, RETRY_AFTER};
+    ///
+    /// let app = App::new().end(end);
+    ///
+    /// async fn end(ctx: &mut Context) -> Result {
+    ///     throw!(
+    ///         StatusCode::SERVICE_UNAVAILABLE, "try again later";
+    ///         with_header(RETRY_AFTER, HeaderValue::from_static("120"))
+    ///     )
+    /// }
+    /// ```
+    pub headers: Box<HeaderMap>,
+
+    /// A machine-readable error code.
+    pub code: Option<String>,
+
+    /// The source error, set when converted from an error.
+    pub source: Option<Arc<dyn Error + Send + Sync>>,
+
+    /// Structured details, rendered by error handlers like problem+json.
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+    pub details: Option<serde_json::Value>,
 }
 
 impl Status {
@@ -129,23 +178,273 @@ impl Status {
             status_code,
             message: message.to_string(),
             expose,
+            headers: Box::default(),
+            code: None,
+            source: None,
+            #[cfg(feature = "json")]
+            details: None,
         }
     }
+
+    /// Append a header.
+    #[inline]
+    pub fn with_header(mut self, name: impl IntoHeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Construct a 413 PAYLOAD TOO LARGE status.
+    #[inline]
+    pub(crate) fn from_payload_too_large(err: PayloadTooLarge) -> Self {
+        Self::new(StatusCode::PAYLOAD_TOO_LARGE, err, true)
+    }
+
+    /// Set the error code.
+    #[inline]
+    pub fn with_code(mut self, code: impl ToString) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    /// Set the source error.
+    #[inline]
+    pub fn with_source(mut self, source: impl 'static + Error + Send + Sync) -> Self {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// Set the details.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the details fail to be serialized.
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+    #[inline]
+    pub fn with_details(mut self, details: impl serde::Serialize) -> Self {
+        self.details = Some(serde_json::to_value(details).expect("fail to serialize details"));
+        self
+    }
+
+    /// Search `PayloadTooLarge` in the source error and its causes.
+    #[inline]
+    pub(crate) fn payload_too_large(&self) -> Option<PayloadTooLarge> {
+        self.chain().find_map(payload_too_large)
+    }
+
+    /// Iterate over the source error and its causes.
+    #[inline]
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn 'static + Error)> {
+        let mut next = self
+            .source
+            .as_deref()
+            .map(|err| err as &(dyn 'static + Error));
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = current.source();
+            Some(current)
+        })
+    }
 }
 
 impl<E> From<E> for Status
 where
-    E: std::error::Error,
+    E: 'static + Error + Send + Sync,
 {
+    /// Convert an error to 500 INTERNAL SERVER ERROR,
+    /// or 413 PAYLOAD TOO LARGE if it's caused by `PayloadTooLarge`.
+    ///
+    /// The error is kept as the source, so it must be `'static + Send + Sync`,
+    /// others can be converted by `Status::new` with their messages.
     #[inline]
     fn from(err: E) -> Self {
-        Self::new(StatusCode::INTERNAL_SERVER_ERROR, err, false)
+        let err_ref: &(dyn 'static + Error) = &err;
+        let too_large =
+            std::iter::successors(Some(err_ref), |err| (*err).source()).find_map(payload_too_large);
+        match too_large {
+            Some(too_large) => Self::from_payload_too_large(too_large).with_source(err),
+            None => Self::new(StatusCode::INTERNAL_SERVER_ERROR, &err, false).with_source(err),
+        }
     }
 }
 
+/// Downcast an error to `PayloadTooLarge`, including the inner error of `io::Error`.
+fn payload_too_large(err: &(dyn 'static + Error)) -> Option<PayloadTooLarge> {
+    if let Some(too_large) = err.downcast_ref::<PayloadTooLarge>() {
+        return Some(*too_large);
+    }
+    err.downcast_ref::<std::io::Error>()
+        .and_then(std::io::Error::get_ref)
+        .and_then(|inner| inner.downcast_ref::<PayloadTooLarge>())
+        .copied()
+}
+
+impl PartialEq for Status {
+    /// Sources are equal only if they are the same error.
+    fn eq(&self, other: &Self) -> bool {
+        let source_eq = match (&self.source, &other.source) {
+            (Some(this), Some(that)) => Arc::ptr_eq(this, that),
+            (None, None) => true,
+            _ => false,
+        };
+        #[cfg(feature = "json")]
+        let details_eq = self.details == other.details;
+        #[cfg(not(feature = "json"))]
+        let details_eq = true;
+        self.status_code == other.status_code
+            && self.message == other.message
+            && self.expose == other.expose
+            && self.headers == other.headers
+            && self.code == other.code
+            && source_eq
+            && details_eq
+    }
+}
+
+impl Eq for Status {}
+
 impl Display for Status {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> StdResult<(), std::fmt::Error> {
         f.write_str(&format!("{}: {}", self.status_code, self.message))
     }
 }
+
+/// ### Error handler
+///
+/// An error handler renders the `Status` thrown by the top middleware into response.
+///
+/// A functional error handler is an async function with signature:
+/// `async fn(&mut Context, Status)`.
+///
+/// ```rust
+/// use roa_core::{App, Context, Status};
+/// use roa_core::http::StatusCode;
+///
+/// async fn handle_error(ctx: &mut Context, status: Status) {
+///     ctx.resp.status = status.status_code;
+///     ctx.resp.write(format!("<h1>{}</h1>", status.status_code));
+/// }
+///
+/// let app = App::new().on_error(handle_error).end(());
+/// ```
+///
+/// You can also delegate to `default_error_handler`,
+/// for example, after reporting the error to an error tracker.
+///
+/// ```rust
+/// use roa_core::{App, Context, Status, default_error_handler};
+///
+/// async fn handle_error(ctx: &mut Context, status: Status) {
+///     if status.status_code.is_server_error() {
+///         // report status
+///     }
+///     default_error_handler(ctx, status).await
+/// }
+///
+/// let app = App::new().on_error(handle_error).end(());
+/// ```
+#[async_trait(?Send)]
+pub trait ErrorHandler<'a, S = ()>: 'static + Sync + Send {
+    /// Handle context and status.
+    async fn handle(&'a self, ctx: &'a mut Context<S>, status: Status);
+}
+
+#[async_trait(?Send)]
+impl<'a, S, T, F> ErrorHandler<'a, S> for T
+where
+    S: 'a,
+    T: 'static + Send + Sync + Fn(&'a mut Context<S>, Status) -> F,
+    F: 'a + Future<Output = ()>,
+{
+    #[inline]
+    async fn handle(&'a self, ctx: &'a mut Context<S>, status: Status) {
+        (self)(ctx, status).await
+    }
+}
+
+/// The default error handler.
+///
+/// It responds the status code, then writes the message to body if it's exposed,
+/// otherwise logs the message and the source chain by `tracing::error!`,
+/// with the request id as a field if it's set.
+pub async fn default_error_handler<S>(ctx: &mut Context<S>, status: Status) {
+    ctx.resp.status = status.status_code;
+    if status.expose {
+        ctx.resp.write(status.message);
+    } else {
+        let request_id = ctx.request_id().cloned();
+        let _ = ctx
+            .exec
+            .spawn_blocking(move || log_status(&status, request_id.as_ref()))
+            .await;
+    }
+}
+
+/// Log an uncaught status with its source chain.
+fn log_status(status: &Status, request_id: Option<&RequestId>) {
+    let request_id = request_id.map(RequestId::as_str);
+    let causes: Vec<String> = status
+        .chain()
+        .map(ToString::to_string)
+        // the message is usually converted from the source
+        .skip_while(|cause| *cause == status.message)
+        .collect();
+    if causes.is_empty() {
+        tracing::error!(request_id, "Uncaught status: {}", status)
+    } else {
+        tracing::error!(
+            request_id,
+            "Uncaught status: {}, caused by: {}",
+            status,
+            causes.join(": ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use http::header::{HeaderValue, WWW_AUTHENTICATE};
+    use http::StatusCode;
+
+    use crate::Status;
+
+    #[test]
+    fn keep_source() {
+        let status = Status::from(io::Error::other("disk full"));
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status.status_code);
+        assert_eq!("disk full", status.message);
+        assert!(!status.expose);
+        let causes: Vec<String> = status.chain().map(ToString::to_string).collect();
+        assert_eq!(vec!["disk full".to_string()], causes);
+        assert_eq!(status, status.clone());
+        assert_ne!(status, Status::from(io::Error::other("disk full")));
+    }
+
+    #[test]
+    fn status_with_options() {
+        let status = status!(
+            StatusCode::UNAUTHORIZED, "login required";
+            with_header(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer")),
+            with_code("unauthorized"),
+        );
+        assert_eq!(StatusCode::UNAUTHORIZED, status.status_code);
+        assert_eq!("login required", status.message);
+        assert!(status.expose);
+        assert_eq!("Bearer", status.headers[WWW_AUTHENTICATE]);
+        assert_eq!(Some("unauthorized"), status.code.as_deref());
+    }
+
+    #[test]
+    fn throw_with_options() {
+        fn throw() -> crate::Result {
+            throw!(StatusCode::BAD_REQUEST; with_code("bad_request"))
+        }
+        let status = throw().unwrap_err();
+        assert_eq!(StatusCode::BAD_REQUEST, status.status_code);
+        assert_eq!("", status.message);
+        assert_eq!(Some("bad_request"), status.code.as_deref());
+    }
+}
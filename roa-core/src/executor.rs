@@ -1,14 +1,27 @@
+use std::any::Any;
+use std::fmt::{self, Display, Formatter};
 use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use futures::channel::oneshot::{channel, Receiver};
+use futures::future::{AbortHandle, Abortable, FutureExt};
 use futures::task::{Context, Poll};
+use futures_timer::Delay;
 use hyper::rt;
+use tokio::sync::Notify;
+use tracing::{Instrument, Span};
 
 /// Future Object
 pub type FutureObj = Pin<Box<dyn 'static + Send + Future<Output = ()>>>;
 
+/// Local future Object, which can be `!Send`.
+pub type LocalFutureObj = Pin<Box<dyn 'static + Future<Output = ()>>>;
+
 /// Blocking task Object
 pub type BlockingObj = Box<dyn 'static + Send + FnOnce()>;
 
@@ -19,16 +32,143 @@ pub trait Spawn {
 
     /// Spawn a blocking task object
     fn spawn_blocking(&self, task: BlockingObj);
+
+    /// Spawn a local future object on the current thread.
+    ///
+    /// Only runtimes serving thread-per-core support it,
+    /// the default implementation drops the future without running it.
+    #[inline]
+    fn spawn_local(&self, fut: LocalFutureObj) {
+        tracing::warn!("spawning local future is not supported by this runtime");
+        drop(fut);
+    }
+
+    /// Construct a future resolving after the duration.
+    ///
+    /// The default implementation is runtime-agnostic, driven by a global timer thread.
+    /// Runtimes with their own timer should override it.
+    #[inline]
+    fn sleep(&self, duration: Duration) -> FutureObj {
+        Box::pin(Delay::new(duration))
+    }
 }
 
 /// A type implementing hyper::rt::Executor
 #[derive(Clone)]
-pub struct Executor(pub(crate) Arc<dyn 'static + Send + Sync + Spawn>);
+pub struct Executor {
+    spawner: Arc<dyn 'static + Send + Sync + Spawn>,
+    tracker: Arc<Tracker>,
+}
 
 /// A handle that awaits the result of a task.
-pub struct JoinHandle<T>(Receiver<T>);
+///
+/// Dropping the handle detaches the task, it keeps running in background.
+/// Use `JoinHandle::abort` to cancel it.
+pub struct JoinHandle<T> {
+    recv: Receiver<thread::Result<T>>,
+    abort: AbortHandle,
+    finished: Arc<AtomicBool>,
+}
+
+/// An error returned by `JoinHandle` if the task is aborted or panicked.
+///
+/// It's converted to a `Status` with 500 INTERNAL SERVER ERROR.
+#[derive(Debug)]
+pub struct JoinError {
+    panic: Option<String>,
+}
+
+/// A counter of alive tasks spawned by an executor.
+#[derive(Default)]
+struct Tracker {
+    alive: AtomicUsize,
+    idle: Notify,
+}
+
+/// A guard held by an alive task, decreasing counter when dropped.
+struct TaskGuard(Arc<Tracker>);
+
+/// A guard marking the task as finished when dropped.
+struct FinishGuard(Arc<AtomicBool>);
+
+impl Drop for FinishGuard {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+impl Drop for TaskGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if self.0.alive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
 
 impl Executor {
+    /// Construct an executor by a runtime.
+    #[inline]
+    pub(crate) fn new(spawner: impl 'static + Send + Sync + Spawn) -> Self {
+        Self {
+            spawner: Arc::new(spawner),
+            tracker: Arc::new(Tracker::default()),
+        }
+    }
+
+    /// Track a new task.
+    #[inline]
+    fn track(&self) -> TaskGuard {
+        self.tracker.alive.fetch_add(1, Ordering::AcqRel);
+        TaskGuard(self.tracker.clone())
+    }
+
+    /// Wait until all tasks spawned by this executor are complete,
+    /// including connections spawned by hyper.
+    pub(crate) async fn idle(&self) {
+        loop {
+            let notified = self.tracker.idle.notified();
+            if self.tracker.alive.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Wrap a future into a tracked task and its handle.
+    /// The task is instrumented with the current span.
+    fn task<Fut>(&self, fut: Fut) -> (impl Future<Output = ()>, JoinHandle<Fut::Output>)
+    where
+        Fut: 'static + Future,
+        Fut::Output: 'static,
+    {
+        let (sender, recv) = channel();
+        let (abort, registration) = AbortHandle::new_pair();
+        let finished = Arc::new(AtomicBool::new(false));
+        // fields are dropped in order, mark the task finished before it's untracked,
+        // so that `idle` never returns before `is_finished` is true.
+        let guards = (FinishGuard(finished.clone()), self.track());
+        let fut = Abortable::new(
+            AssertUnwindSafe(fut.instrument(Span::current())).catch_unwind(),
+            registration,
+        );
+        let task = async move {
+            if let Ok(result) = fut.await {
+                if sender.send(result).is_err() {
+                    // handler is dropped, do nothing.
+                };
+            }
+            drop(guards);
+        };
+        let handle = JoinHandle {
+            recv,
+            abort,
+            finished,
+        };
+        (task, handle)
+    }
+
     /// Spawn a future by app runtime
     #[inline]
     pub fn spawn<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
@@ -36,16 +176,36 @@ impl Executor {
         Fut: 'static + Send + Future,
         Fut::Output: 'static + Send,
     {
-        let (sender, recv) = channel();
-        self.0.spawn(Box::pin(async move {
-            if sender.send(fut.await).is_err() {
-                // handler is dropped, do nothing.
-            };
-        }));
-        JoinHandle(recv)
+        let (task, handle) = self.task(fut);
+        self.spawner.spawn(Box::pin(task));
+        handle
     }
 
-    /// Spawn a blocking task by app runtime
+    /// Spawn a `!Send` future on the current thread by app runtime.
+    ///
+    /// It's only supported by apps served thread-per-core,
+    /// otherwise the future is dropped and the handle resolves to a cancelled `JoinError`.
+    #[inline]
+    pub fn spawn_local<Fut>(&self, fut: Fut) -> JoinHandle<Fut::Output>
+    where
+        Fut: 'static + Future,
+        Fut::Output: 'static,
+    {
+        let (task, handle) = self.task(fut);
+        self.spawner.spawn_local(Box::pin(task));
+        handle
+    }
+
+    /// Sleep for the duration by app runtime.
+    #[inline]
+    pub fn sleep(&self, duration: Duration) -> FutureObj {
+        self.spawner.sleep(duration)
+    }
+
+    /// Spawn a blocking task by app runtime.
+    ///
+    /// A blocking task can only be aborted before it starts.
+    /// It runs in the current span, like spawned futures.
     #[inline]
     pub fn spawn_blocking<T, R>(&self, task: T) -> JoinHandle<R>
     where
@@ -53,21 +213,114 @@ impl Executor {
         R: 'static + Send,
     {
         let (sender, recv) = channel();
-        self.0.spawn_blocking(Box::new(|| {
-            if sender.send(task()).is_err() {
+        let (abort, _) = AbortHandle::new_pair();
+        let finished = Arc::new(AtomicBool::new(false));
+        // fields are dropped in order, mark the task finished before it's untracked,
+        // so that `idle` never returns before `is_finished` is true.
+        let guards = (FinishGuard(finished.clone()), self.track());
+        let aborted = abort.clone();
+        let span = Span::current();
+        self.spawner.spawn_blocking(Box::new(move || {
+            let _entered = span.entered();
+            if !aborted.is_aborted() && sender.send(catch_unwind(AssertUnwindSafe(task))).is_err() {
                 // handler is dropped, do nothing.
             };
+            drop(guards);
         }));
-        JoinHandle(recv)
+        JoinHandle {
+            recv,
+            abort,
+            finished,
+        }
     }
 }
 
+impl<T> JoinHandle<T> {
+    /// Abort the task, then the handle resolves to a cancelled `JoinError`
+    /// unless the task has finished.
+    #[inline]
+    pub fn abort(&self) {
+        self.abort.abort()
+    }
+
+    /// Whether the task has finished, completed, panicked or aborted.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+impl JoinError {
+    /// Whether the task is aborted.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.panic.is_none()
+    }
+
+    /// Whether the task panicked.
+    #[inline]
+    pub fn is_panic(&self) -> bool {
+        self.panic.is_some()
+    }
+
+    /// Construct from a panic payload.
+    fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown panic".to_string(),
+            },
+        };
+        Self {
+            panic: Some(message),
+        }
+    }
+}
+
+impl Display for JoinError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.panic {
+            Some(message) => write!(f, "task panicked: {}", message),
+            None => f.write_str("task was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
 impl<T> Future for JoinHandle<T> {
-    type Output = T;
+    type Output = Result<T, JoinError>;
     #[inline]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let ready = futures::ready!(Pin::new(&mut self.0).poll(cx));
-        Poll::Ready(ready.expect("receiver in JoinHandle shouldn't be canceled"))
+        Poll::Ready(match futures::ready!(Pin::new(&mut self.recv).poll(cx)) {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(payload)) => Err(JoinError::from_panic(payload)),
+            // the task is aborted
+            Err(_) => Err(JoinError { panic: None }),
+        })
+    }
+}
+
+/// A hyper executor spawning connections on the current thread,
+/// so the futures of connections can be `!Send`.
+#[cfg(feature = "runtime")]
+#[derive(Clone)]
+pub(crate) struct LocalExecutor(pub(crate) Executor);
+
+#[cfg(feature = "runtime")]
+impl<F> rt::Executor<F> for LocalExecutor
+where
+    F: 'static + Future,
+{
+    #[inline]
+    fn execute(&self, fut: F) {
+        let guard = self.0.track();
+        self.0.spawner.spawn_local(Box::pin(async move {
+            let _ = fut.await;
+            drop(guard);
+        }));
     }
 }
 
@@ -78,16 +331,16 @@ where
 {
     #[inline]
     fn execute(&self, fut: F) {
-        self.0.spawn(Box::pin(async move {
+        let guard = self.track();
+        self.spawner.spawn(Box::pin(async move {
             let _ = fut.await;
+            drop(guard);
         }));
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
-
     use super::{BlockingObj, Executor, FutureObj, Spawn};
 
     pub struct Exec;
@@ -104,13 +357,78 @@ mod tests {
 
     #[tokio::test]
     async fn spawn() {
-        let exec = Executor(Arc::new(Exec));
-        assert_eq!(1, exec.spawn(async { 1 }).await);
+        let exec = Executor::new(Exec);
+        assert_eq!(1, exec.spawn(async { 1 }).await.unwrap());
     }
 
     #[tokio::test]
     async fn spawn_blocking() {
-        let exec = Executor(Arc::new(Exec));
-        assert_eq!(1, exec.spawn_blocking(|| 1).await);
+        let exec = Executor::new(Exec);
+        assert_eq!(1, exec.spawn_blocking(|| 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn spawn_local_unsupported() {
+        let exec = Executor::new(Exec);
+        let err = exec.spawn_local(async { 1 }).await.unwrap_err();
+        assert!(err.is_cancelled());
+        exec.idle().await;
+    }
+
+    #[tokio::test]
+    async fn idle() {
+        let exec = Executor::new(Exec);
+        exec.idle().await;
+        let (sender, recv) = futures::channel::oneshot::channel::<()>();
+        exec.spawn(async move {
+            let _ = recv.await;
+        });
+        let idle = exec.idle();
+        futures::pin_mut!(idle);
+        assert!(futures::poll!(idle.as_mut()).is_pending());
+        sender.send(()).unwrap();
+        idle.await;
+    }
+
+    #[tokio::test]
+    async fn abort() {
+        let exec = Executor::new(Exec);
+        let handle = exec.spawn(futures::future::pending::<()>());
+        assert!(!handle.is_finished());
+        handle.abort();
+        let err = handle.await.unwrap_err();
+        assert!(err.is_cancelled());
+        assert_eq!("task was cancelled", err.to_string());
+        exec.idle().await;
+    }
+
+    #[tokio::test]
+    async fn panic() {
+        let exec = Executor::new(Exec);
+        let err = exec.spawn(async { panic!("boom") }).await.unwrap_err();
+        assert!(err.is_panic());
+        assert_eq!("task panicked: boom", err.to_string());
+
+        let err = exec
+            .spawn_blocking(|| panic!("{}", "blocking boom"))
+            .await
+            .unwrap_err();
+        assert_eq!("task panicked: blocking boom", err.to_string());
+
+        let status: crate::Status = err.into();
+        assert_eq!(http::StatusCode::INTERNAL_SERVER_ERROR, status.status_code);
+    }
+
+    #[tokio::test]
+    async fn is_finished() {
+        let exec = Executor::new(Exec);
+        let handle = exec.spawn_blocking(|| 1);
+        exec.idle().await;
+        assert!(handle.is_finished());
+        assert_eq!(1, handle.await.unwrap());
+        let handle = exec.spawn(async { 2 });
+        exec.idle().await;
+        assert!(handle.is_finished());
+        assert_eq!(2, handle.await.unwrap());
     }
 }
@@ -1,6 +1,13 @@
+use std::future::Future;
+use std::pin::pin;
+use std::rc::Rc;
 use std::sync::Arc;
 
-use crate::{async_trait, Context, Endpoint, Middleware, Next, Result};
+use futures::future::LocalBoxFuture;
+use http::{Method, StatusCode};
+
+use crate::predicate::{methods, not, path_prefix, Methods, Not, PathPrefix, Predicate};
+use crate::{Context, Endpoint, Meta, Middleware, Next, Result};
 
 /// A set of method to chain middleware/endpoint to middleware
 /// or make middleware shared.
@@ -21,39 +28,326 @@ pub trait MiddlewareExt<S>: Sized + for<'a> Middleware<'a, S> {
         Chain(self, next)
     }
 
-    /// Make middleware shared.
+    /// Make middleware shared, its type is erased and its future is boxed.
     fn shared(self) -> Shared<S>
     where
+        Self: Send + Sync,
         S: 'static,
     {
         Shared(Arc::new(self))
     }
+
+    /// Make middleware shared without requiring `Send + Sync`,
+    /// so it can only be served on the current thread, see `Local`.
+    fn shared_local(self) -> LocalShared<S>
+    where
+        S: 'static,
+    {
+        LocalShared(Rc::new(self))
+    }
+
+    /// Apply this middleware only if the predicate is true,
+    /// otherwise it's skipped and the next is called directly.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, MiddlewareExt, Next, Result};
+    /// use roa_core::http::Method;
+    ///
+    /// async fn gate(ctx: &mut Context, next: Next<'_>) -> Result {
+    ///     next.await
+    /// }
+    ///
+    /// let app = App::new()
+    ///     .gate(gate.when(|ctx: &Context| ctx.method() != Method::OPTIONS))
+    ///     .end(());
+    /// ```
+    fn when<P>(self, predicate: P) -> When<Self, P>
+    where
+        P: Predicate<S>,
+    {
+        When(self, predicate)
+    }
+
+    /// Apply this middleware unless the predicate is true.
+    fn unless<P>(self, predicate: P) -> When<Self, Not<P>>
+    where
+        P: Predicate<S>,
+    {
+        When(self, not(predicate))
+    }
+
+    /// Apply this middleware only on any of the path prefixes.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, MiddlewareExt, Next, Result};
+    ///
+    /// async fn gate(ctx: &mut Context, next: Next<'_>) -> Result {
+    ///     next.await
+    /// }
+    ///
+    /// let app = App::new().gate(gate.for_paths(["/assets"])).end(());
+    /// ```
+    fn for_paths<I>(self, prefixes: I) -> When<Self, PathPrefix>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        When(self, path_prefix(prefixes))
+    }
+
+    /// Apply this middleware only on any of the methods.
+    fn for_methods<I>(self, methods_: I) -> When<Self, Methods>
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        When(self, methods(methods_))
+    }
 }
 
 /// Extra methods of endpoint.
 pub trait EndpointExt<S>: Sized + for<'a> Endpoint<'a, S> {
-    /// Box an endpoint.
+    /// Box an endpoint, its type is erased and its future is boxed.
     fn boxed(self) -> Boxed<S>
     where
+        Self: Send + Sync,
         S: 'static,
     {
         Boxed(Box::new(self))
     }
+
+    /// Box an endpoint without requiring `Send + Sync`,
+    /// so it can only be served on the current thread, see `Local`.
+    fn boxed_local(self) -> LocalBoxed<S>
+    where
+        S: 'static,
+    {
+        LocalBoxed(Box::new(self))
+    }
+
+    /// Fall back to another endpoint if this one fails with `404 Not Found`.
+    /// Statuses to fall back on can be configured by `Cascade::fallback_on`.
+    ///
+    /// Calling `or` on a cascade appends to it, so all endpoints share the same statuses.
+    /// The status and headers of response are restored and the body is cleared
+    /// before calling the fallback. The request body is not read by the cascade,
+    /// so the fallback can still consume it if the previous endpoint didn't.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa_core::{App, Context, EndpointExt, Result, throw};
+    /// use roa_core::http::StatusCode;
+    ///
+    /// async fn api(ctx: &mut Context) -> Result {
+    ///     throw!(StatusCode::NOT_FOUND)
+    /// }
+    ///
+    /// async fn index(ctx: &mut Context) -> Result {
+    ///     ctx.resp.write("<html></html>");
+    ///     Ok(())
+    /// }
+    ///
+    /// let app = App::new().end(
+    ///     api.or(index)
+    ///         .fallback_on([StatusCode::NOT_FOUND, StatusCode::METHOD_NOT_ALLOWED]),
+    /// );
+    /// ```
+    fn or<E>(self, fallback: E) -> Cascade<Single<Self>, E>
+    where
+        E: for<'a> Endpoint<'a, S>,
+    {
+        Cascade {
+            first: Single(self),
+            fallback,
+            statuses: vec![StatusCode::NOT_FOUND],
+        }
+    }
 }
 
 impl<S, T> MiddlewareExt<S> for T where T: for<'a> Middleware<'a, S> {}
 impl<S, T> EndpointExt<S> for T where T: for<'a> Endpoint<'a, S> {}
 
+/// An object safe version of `Middleware`, boxing the future.
+/// It's implemented for all middlewares.
+pub trait DynMiddleware<'a, S = ()>: 'static {
+    /// Handle context and next, return a boxed future.
+    fn handle_boxed(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> LocalBoxFuture<'a, Result>;
+}
+
+/// An object safe version of `Endpoint`, boxing the future.
+/// It's implemented for all endpoints.
+pub trait DynEndpoint<'a, S = ()>: 'static {
+    /// Call this endpoint, return a boxed future.
+    fn call_boxed(&'a self, ctx: &'a mut Context<S>) -> LocalBoxFuture<'a, Result>;
+
+    /// Methods accepted by this endpoint and their metadata, see `Endpoint::methods`.
+    fn dyn_methods(&self) -> Option<Vec<(Method, Meta)>>;
+}
+
+impl<'a, S, T> DynMiddleware<'a, S> for T
+where
+    T: Middleware<'a, S>,
+{
+    #[inline]
+    fn handle_boxed(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> LocalBoxFuture<'a, Result> {
+        Box::pin(self.handle(ctx, next))
+    }
+}
+
+impl<'a, S, T> DynEndpoint<'a, S> for T
+where
+    T: Endpoint<'a, S>,
+{
+    #[inline]
+    fn call_boxed(&'a self, ctx: &'a mut Context<S>) -> LocalBoxFuture<'a, Result> {
+        Box::pin(self.call(ctx))
+    }
+
+    #[inline]
+    fn dyn_methods(&self) -> Option<Vec<(Method, Meta)>> {
+        self.methods()
+    }
+}
+
 /// A middleware composing and executing other middlewares in a stack-like manner.
 pub struct Chain<T, U>(T, U);
 
+/// A middleware applied only if the predicate is true,
+/// constructed by `MiddlewareExt::when` and its variants.
+pub struct When<M, P>(M, P);
+
+/// An endpoint trying endpoints in order, constructed by `EndpointExt::or`.
+pub struct Cascade<L, E> {
+    first: L,
+    fallback: E,
+    statuses: Vec<StatusCode>,
+}
+
+/// The first endpoint of a `Cascade`.
+pub struct Single<E>(E);
+
+/// Endpoints of a `Cascade` before its last fallback.
+pub struct Link<L, E>(L, E);
+
+/// Endpoints of a `Cascade` tried in order, falling back on the statuses of cascade.
+pub trait Fallible<'a, S>: 'static {
+    /// Call endpoints in order until one doesn't fail with the statuses.
+    fn call_until(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        statuses: &'a [StatusCode],
+    ) -> impl 'a + Future<Output = Result>;
+}
+
 /// Shared middleware.
-pub struct Shared<S>(Arc<dyn for<'a> Middleware<'a, S>>);
+pub struct Shared<S>(Arc<dyn Send + Sync + for<'a> DynMiddleware<'a, S>>);
 
 /// Boxed endpoint.
-pub struct Boxed<S>(Box<dyn for<'a> Endpoint<'a, S>>);
+pub struct Boxed<S>(Box<dyn Send + Sync + for<'a> DynEndpoint<'a, S>>);
+
+/// Shared middleware, which may be `!Send` or `!Sync`.
+pub struct LocalShared<S>(Rc<dyn for<'a> DynMiddleware<'a, S>>);
+
+/// Boxed endpoint, which may be `!Send` or `!Sync`.
+pub struct LocalBoxed<S>(Box<dyn for<'a> DynEndpoint<'a, S>>);
+
+/// A kind of type erasure, deciding whether endpoints and middlewares
+/// must be `Send + Sync` to be erased, by `roa::router::Router` for example.
+///
+/// `Threaded` erases them into `Boxed` and `Shared`, which can be served by multi-threaded runtimes.
+/// `Local` erases them into `LocalBoxed` and `LocalShared`, which can hold `Rc` or `RefCell`,
+/// but can only be served on the current thread, by `App::serve_local` for example.
+pub trait Erasure<S>: 'static {
+    /// The erased endpoint.
+    type Endpoint: for<'a> Endpoint<'a, S>;
+
+    /// The erased middleware.
+    type Middleware: Clone + for<'a> Middleware<'a, S>;
+}
+
+/// Erase the type of `T`, implemented by `Threaded` for `T: Send + Sync` and by `Local` for any `T`.
+pub trait Erase<S, T>: Erasure<S> {
+    /// Erase an endpoint.
+    fn boxed(endpoint: T) -> Self::Endpoint
+    where
+        T: for<'a> Endpoint<'a, S>;
+
+    /// Erase a middleware.
+    fn shared(middleware: T) -> Self::Middleware
+    where
+        T: for<'a> Middleware<'a, S>;
+}
+
+/// Type erasure requiring `Send + Sync`, see `Erasure`.
+#[derive(Debug, Clone, Copy)]
+pub enum Threaded {}
+
+/// Type erasure without `Send + Sync`, see `Erasure`.
+#[derive(Debug, Clone, Copy)]
+pub enum Local {}
+
+impl<S: 'static> Erasure<S> for Threaded {
+    type Endpoint = Boxed<S>;
+    type Middleware = Shared<S>;
+}
+
+impl<S: 'static> Erasure<S> for Local {
+    type Endpoint = LocalBoxed<S>;
+    type Middleware = LocalShared<S>;
+}
+
+impl<S, T> Erase<S, T> for Threaded
+where
+    S: 'static,
+    T: Send + Sync,
+{
+    #[inline]
+    fn boxed(endpoint: T) -> Boxed<S>
+    where
+        T: for<'a> Endpoint<'a, S>,
+    {
+        endpoint.boxed()
+    }
+
+    #[inline]
+    fn shared(middleware: T) -> Shared<S>
+    where
+        T: for<'a> Middleware<'a, S>,
+    {
+        middleware.shared()
+    }
+}
+
+impl<S, T> Erase<S, T> for Local
+where
+    S: 'static,
+{
+    #[inline]
+    fn boxed(endpoint: T) -> LocalBoxed<S>
+    where
+        T: for<'a> Endpoint<'a, S>,
+    {
+        endpoint.boxed_local()
+    }
+
+    #[inline]
+    fn shared(middleware: T) -> LocalShared<S>
+    where
+        T: for<'a> Middleware<'a, S>,
+    {
+        middleware.shared_local()
+    }
+}
 
-#[async_trait(?Send)]
 impl<'a, S, T, U> Middleware<'a, S> for Chain<T, U>
 where
     U: Middleware<'a, S>,
@@ -62,19 +356,37 @@ where
     #[inline]
     async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
         let ptr = ctx as *mut Context<S>;
-        let mut next = self.1.handle(unsafe { &mut *ptr }, next);
+        let mut next = pin!(self.1.handle(unsafe { &mut *ptr }, next));
         self.0.handle(ctx, &mut next).await
     }
 }
 
-#[async_trait(?Send)]
+impl<'a, S, M, P> Middleware<'a, S> for When<M, P>
+where
+    M: Middleware<'a, S>,
+    P: Predicate<S>,
+{
+    #[inline]
+    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
+        if self.1.test(ctx) {
+            self.0.handle(ctx, next).await
+        } else {
+            next.await
+        }
+    }
+}
+
 impl<'a, S> Middleware<'a, S> for Shared<S>
 where
     S: 'static,
 {
     #[inline]
-    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
-        self.0.handle(ctx, next).await
+    fn handle(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> impl 'a + Future<Output = Result> {
+        self.0.handle_boxed(ctx, next)
     }
 }
 
@@ -85,18 +397,138 @@ impl<S> Clone for Shared<S> {
     }
 }
 
-#[async_trait(?Send)]
+impl<'a, S> Middleware<'a, S> for LocalShared<S>
+where
+    S: 'static,
+{
+    #[inline]
+    fn handle(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> impl 'a + Future<Output = Result> {
+        self.0.handle_boxed(ctx, next)
+    }
+}
+
+impl<S> Clone for LocalShared<S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<L, E> Cascade<L, E> {
+    /// Set statuses to fall back on for all endpoints of the cascade, `404 Not Found` by default.
+    pub fn fallback_on(mut self, statuses: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Append another endpoint to fall back to, sharing statuses of this cascade.
+    pub fn or<F>(self, fallback: F) -> Cascade<Link<L, E>, F> {
+        Cascade {
+            first: Link(self.first, self.fallback),
+            fallback,
+            statuses: self.statuses,
+        }
+    }
+}
+
+/// Call the endpoint first, then the fallback if the endpoint fails with the statuses.
+async fn fall_back<'a, S, A, B>(
+    first: &'a A,
+    fallback: &'a B,
+    ctx: &'a mut Context<S>,
+    statuses: &'a [StatusCode],
+) -> Result
+where
+    A: for<'b> Fallible<'b, S>,
+    B: Endpoint<'a, S>,
+{
+    let status_code = ctx.resp.status;
+    let headers = ctx.resp.headers.clone();
+    match first.call_until(ctx, statuses).await {
+        Err(status) if statuses.contains(&status.status_code) => {
+            ctx.resp.status = status_code;
+            ctx.resp.headers = headers;
+            ctx.resp.body = Default::default();
+            fallback.call(ctx).await
+        }
+        result => result,
+    }
+}
+
+impl<'a, S, E> Fallible<'a, S> for Single<E>
+where
+    E: Endpoint<'a, S>,
+{
+    #[inline]
+    fn call_until(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        _statuses: &'a [StatusCode],
+    ) -> impl 'a + Future<Output = Result> {
+        self.0.call(ctx)
+    }
+}
+
+impl<'a, S, L, E> Fallible<'a, S> for Link<L, E>
+where
+    L: for<'b> Fallible<'b, S>,
+    E: Endpoint<'a, S>,
+{
+    #[inline]
+    fn call_until(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        statuses: &'a [StatusCode],
+    ) -> impl 'a + Future<Output = Result> {
+        fall_back(&self.0, &self.1, ctx, statuses)
+    }
+}
+
+impl<'a, S, L, E> Endpoint<'a, S> for Cascade<L, E>
+where
+    L: for<'b> Fallible<'b, S>,
+    E: Endpoint<'a, S>,
+{
+    #[inline]
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result> {
+        fall_back(&self.first, &self.fallback, ctx, &self.statuses)
+    }
+}
+
 impl<'a, S> Endpoint<'a, S> for Boxed<S>
 where
     S: 'static,
 {
     #[inline]
-    async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
-        self.0.call(ctx).await
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result> {
+        self.0.call_boxed(ctx)
+    }
+
+    #[inline]
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        self.0.dyn_methods()
+    }
+}
+
+impl<'a, S> Endpoint<'a, S> for LocalBoxed<S>
+where
+    S: 'static,
+{
+    #[inline]
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result> {
+        self.0.call_boxed(ctx)
+    }
+
+    #[inline]
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        self.0.dyn_methods()
     }
 }
 
-#[async_trait(?Send)]
 impl<'a, S, T, U> Endpoint<'a, S> for Chain<T, U>
 where
     U: Endpoint<'a, S>,
@@ -105,9 +537,13 @@ where
     #[inline]
     async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
         let ptr = ctx as *mut Context<S>;
-        let mut next = self.1.call(unsafe { &mut *ptr });
+        let mut next = pin!(self.1.call(unsafe { &mut *ptr }));
         self.0.handle(ctx, &mut next).await
     }
+    #[inline]
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        self.1.methods()
+    }
 }
 
 #[cfg(all(test, feature = "runtime"))]
@@ -117,7 +553,7 @@ mod tests {
     use futures::lock::Mutex;
     use http::StatusCode;
 
-    use crate::{async_trait, App, Context, Middleware, Next, Request, Status};
+    use crate::{App, Context, EndpointExt, Middleware, MiddlewareExt, Next, Request, Status};
 
     struct Pusher {
         data: usize,
@@ -130,7 +566,6 @@ mod tests {
         }
     }
 
-    #[async_trait(?Send)]
     impl<'a> Middleware<'a, ()> for Pusher {
         async fn handle(&'a self, _ctx: &'a mut Context, next: Next<'a>) -> Result<(), Status> {
             self.vector.lock().await.push(self.data);
@@ -164,4 +599,103 @@ mod tests {
         }
         Ok(())
     }
+
+    #[tokio::test]
+    async fn conditional() -> Result<(), Box<dyn std::error::Error>> {
+        let vector = Arc::new(Mutex::new(Vec::new()));
+        let service = App::new()
+            .gate(Pusher::new(0, vector.clone()).for_paths(["/api"]))
+            .gate(
+                Pusher::new(1, vector.clone()).unless(|ctx: &Context| ctx.uri().query().is_some()),
+            )
+            .gate(Pusher::new(2, vector.clone()).for_methods([http::Method::POST]))
+            .end(())
+            .http_service();
+        for uri in ["/api/user", "/api?id=1", "/health"] {
+            let mut req = Request::default();
+            req.uri = uri.parse()?;
+            let resp = service.clone().serve(req).await;
+            assert_eq!(StatusCode::OK, resp.status);
+        }
+        assert_eq!(vec![0, 1, 1, 0, 0, 0, 1, 1], *vector.lock().await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cascade() -> Result<(), Box<dyn std::error::Error>> {
+        async fn not_found(_ctx: &mut Context) -> Result<(), Status> {
+            Err(Status::new(StatusCode::NOT_FOUND, "", false))
+        }
+        async fn not_allowed(_ctx: &mut Context) -> Result<(), Status> {
+            Err(Status::new(StatusCode::METHOD_NOT_ALLOWED, "", false))
+        }
+        async fn echo(ctx: &mut Context) -> Result<(), Status> {
+            let reader = ctx.req.reader();
+            ctx.resp.write_reader(reader);
+            Ok(())
+        }
+        let service = App::new()
+            .end(not_found.or(not_allowed).or(echo))
+            .http_service();
+        let resp = service.serve(Request::default()).await;
+        assert_eq!(StatusCode::METHOD_NOT_ALLOWED, resp.status);
+
+        let service = App::new()
+            .end(
+                not_found
+                    .or(not_allowed)
+                    .or(echo)
+                    .fallback_on([StatusCode::NOT_FOUND, StatusCode::METHOD_NOT_ALLOWED]),
+            )
+            .http_service();
+        let req: Request = http::Request::new(hyper::Body::from("Hello, World")).into();
+        let resp = service.serve(req).await;
+        assert_eq!(StatusCode::OK, resp.status);
+        let data = hyper::body::to_bytes(resp.body).await?;
+        assert_eq!("Hello, World", data);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cascade_shares_statuses() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Clone)]
+        struct Tried;
+
+        async fn not_allowed(_ctx: &mut Context) -> Result<(), Status> {
+            Err(Status::new(StatusCode::METHOD_NOT_ALLOWED, "", false))
+        }
+        async fn files(ctx: &mut Context) -> Result<(), Status> {
+            ctx.insert_ext(Tried);
+            ctx.resp.status = StatusCode::ACCEPTED;
+            ctx.resp.headers.insert("x-files", "1".parse()?);
+            ctx.resp.write("partial");
+            Err(Status::new(StatusCode::NOT_FOUND, "", false))
+        }
+        async fn index(ctx: &mut Context) -> Result<(), Status> {
+            assert!(ctx.ext::<Tried>().is_some());
+            ctx.resp.write("index");
+            Ok(())
+        }
+        async fn gate(ctx: &mut Context, next: Next<'_>) -> Result<(), Status> {
+            ctx.resp.headers.insert("x-gate", "1".parse()?);
+            next.await
+        }
+
+        let service = App::new()
+            .gate(gate)
+            .end(
+                not_allowed
+                    .or(files)
+                    .or(index)
+                    .fallback_on([StatusCode::NOT_FOUND, StatusCode::METHOD_NOT_ALLOWED]),
+            )
+            .http_service();
+        let resp = service.serve(Request::default()).await;
+        assert_eq!(StatusCode::OK, resp.status);
+        assert_eq!("1", resp.headers["x-gate"]);
+        assert!(!resp.headers.contains_key("x-files"));
+        let data = hyper::body::to_bytes(resp.body).await?;
+        assert_eq!("index", data);
+        Ok(())
+    }
 }
@@ -4,35 +4,48 @@
 
 mod app;
 mod body;
+mod cancel;
+mod conn;
 mod context;
 mod err;
 mod executor;
 mod group;
+mod meta;
 mod middleware;
+pub mod predicate;
 mod request;
 mod response;
 mod state;
 
 #[doc(inline)]
-pub use app::{AddrStream, App};
+pub use app::{AddrStream, App, Graceful, HttpConfig, HttpService, Shutdown};
 pub use async_trait::async_trait;
 #[doc(inline)]
 pub use body::Body;
 #[doc(inline)]
-pub use context::{Context, Variable};
+pub use cancel::CancellationToken;
 #[doc(inline)]
-pub use err::{Result, Status};
+pub use conn::{ConnInfo, TlsInfo};
 #[doc(inline)]
-pub use executor::{Executor, JoinHandle, Spawn};
+pub use context::{Context, RequestId, Variable};
 #[doc(inline)]
-pub use group::{Boxed, Chain, EndpointExt, MiddlewareExt, Shared};
+pub use err::{default_error_handler, ErrorHandler, Result, Status};
+#[doc(inline)]
+pub use executor::{Executor, JoinError, JoinHandle, Spawn};
+#[doc(inline)]
+pub use group::{
+    Boxed, Cascade, Chain, DynEndpoint, DynMiddleware, EndpointExt, Erase, Erasure, Local,
+    LocalBoxed, LocalShared, MiddlewareExt, Shared, Threaded, When,
+};
 pub use http;
 pub use hyper::server::accept::Accept;
 pub use hyper::server::Server;
 #[doc(inline)]
+pub use meta::Meta;
+#[doc(inline)]
 pub use middleware::{Endpoint, Middleware, Next};
 #[doc(inline)]
-pub use request::Request;
+pub use request::{PayloadTooLarge, Request};
 #[doc(inline)]
 pub use response::Response;
 #[doc(inline)]
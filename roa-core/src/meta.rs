@@ -0,0 +1,99 @@
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+
+use crate::context::Extensions;
+
+/// Metadata of an endpoint on a method, listed by `Endpoint::methods`.
+///
+/// New fields may be added, construct it by `Meta::new` and builder methods.
+/// Other crates can attach their own metadata by `Meta::with`, keyed by type.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa_core::Meta;
+///
+/// #[derive(Clone)]
+/// struct Deprecated(bool);
+///
+/// let meta = Meta::new()
+///     .summary("List users")
+///     .tag("user")
+///     .with(Deprecated(true));
+/// assert_eq!(Some("List users"), meta.summary.as_deref());
+/// assert_eq!(vec!["user".to_string()], meta.tags);
+/// assert!(meta.get::<Deprecated>().unwrap().0);
+/// ```
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub struct Meta {
+    /// A short summary.
+    pub summary: Option<String>,
+
+    /// A verbose description.
+    pub description: Option<String>,
+
+    /// A unique id of the operation.
+    pub operation_id: Option<String>,
+
+    /// Tags for grouping.
+    pub tags: Vec<String>,
+
+    extensions: Extensions,
+}
+
+impl Meta {
+    /// Construct empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a short summary.
+    pub fn summary(mut self, summary: impl ToString) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Set a verbose description.
+    pub fn description(mut self, description: impl ToString) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set a unique id of the operation.
+    pub fn operation_id(mut self, operation_id: impl ToString) -> Self {
+        self.operation_id = Some(operation_id.to_string());
+        self
+    }
+
+    /// Add a tag for grouping.
+    pub fn tag(mut self, tag: impl ToString) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Attach a value, replacing the old one of the same type.
+    pub fn with<T>(mut self, value: T) -> Self
+    where
+        T: Any + Clone + Send + Sync,
+    {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// Get the attached value of type `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.extensions.get()
+    }
+}
+
+impl Debug for Meta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Meta")
+            .field("summary", &self.summary)
+            .field("description", &self.description)
+            .field("operation_id", &self.operation_id)
+            .field("tags", &self.tags)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,9 +1,9 @@
 use std::future::Future;
 
 use http::header::LOCATION;
-use http::{StatusCode, Uri};
+use http::{Method, StatusCode, Uri};
 
-use crate::{async_trait, throw, Context, Result, Status};
+use crate::{throw, Context, Meta, Result, Status};
 
 /// ### Middleware
 ///
@@ -37,14 +37,13 @@ use crate::{async_trait, throw, Context, Result, Status};
 /// You can implement custom `Middleware` for other types.
 ///
 /// ```rust
-/// use roa_core::{App, Middleware, Context, Next, Result, async_trait};
+/// use roa_core::{App, Middleware, Context, Next, Result};
 /// use std::sync::Arc;
 /// use std::time::Instant;
 ///
 ///
 /// struct Logger;
 ///
-/// #[async_trait(?Send)]
 /// impl <'a> Middleware<'a> for Logger {
 ///     async fn handle(&'a self, ctx: &'a mut Context, next: Next<'a>) -> Result {
 ///         let start = Instant::now();
@@ -56,22 +55,35 @@ use crate::{async_trait, throw, Context, Result, Status};
 ///
 /// let app = App::new().gate(Logger);
 /// ```
-#[async_trait(?Send)]
-pub trait Middleware<'a, S = ()>: 'static + Sync + Send {
+///
+/// The future returned by `handle` is not boxed, so a chain of middlewares is composed
+/// into a single future without allocation. Use `MiddlewareExt::shared`
+/// to erase the type of a middleware, which boxes the future.
+///
+/// Middlewares and endpoints need to be `Send + Sync` only to be served by multi-threaded runtimes,
+/// they can hold `Rc` or `RefCell` when the app is served thread-per-core by `App::serve_local`.
+pub trait Middleware<'a, S = ()>: 'static {
     /// Handle context and next, return status.
-    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result;
+    fn handle(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> impl 'a + Future<Output = Result>;
 }
 
-#[async_trait(?Send)]
 impl<'a, S, T, F> Middleware<'a, S> for T
 where
     S: 'a,
-    T: 'static + Send + Sync + Fn(&'a mut Context<S>, Next<'a>) -> F,
+    T: 'static + Fn(&'a mut Context<S>, Next<'a>) -> F,
     F: 'a + Future<Output = Result>,
 {
     #[inline]
-    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
-        (self)(ctx, next).await
+    fn handle(
+        &'a self,
+        ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> impl 'a + Future<Output = Result> {
+        (self)(ctx, next)
     }
 }
 
@@ -142,14 +154,13 @@ where
 /// You can implement custom `Endpoint` for your types.
 ///
 /// ```rust
-/// use roa_core::{App, Endpoint, Context, Next, Result, async_trait};
+/// use roa_core::{App, Endpoint, Context, Next, Result};
 ///
 /// fn is_endpoint(endpoint: impl for<'a> Endpoint<'a>) {
 /// }
 ///
 /// struct Service;
 ///
-/// #[async_trait(?Send)]
 /// impl <'a> Endpoint<'a> for Service {
 ///     async fn call(&'a self, ctx: &'a mut Context) -> Result {
 ///         Ok(())
@@ -158,37 +169,49 @@ where
 ///
 /// let app = App::new().end(Service);
 /// ```
-#[async_trait(?Send)]
-pub trait Endpoint<'a, S = ()>: 'static + Sync + Send {
+///
+/// Like middlewares, the future returned by `call` is not boxed.
+/// Use `EndpointExt::boxed` to erase the type of an endpoint.
+pub trait Endpoint<'a, S = ()>: 'static {
     /// Call this endpoint.
-    async fn call(&'a self, ctx: &'a mut Context<S>) -> Result;
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result>;
+
+    /// Methods accepted by this endpoint and their metadata, for introspection like route listing.
+    ///
+    /// The default implementation returns `None`, which means any method may be accepted.
+    /// Wrappers of endpoints should forward it.
+    #[inline]
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        None
+    }
 }
 
-#[async_trait(?Send)]
 impl<'a, S, T, F> Endpoint<'a, S> for T
 where
     S: 'a,
-    T: 'static + Send + Sync + Fn(&'a mut Context<S>) -> F,
+    T: 'static + Fn(&'a mut Context<S>) -> F,
     F: 'a + Future<Output = Result>,
 {
     #[inline]
-    async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
-        (self)(ctx).await
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result> {
+        (self)(ctx)
     }
 }
 
 /// blank middleware.
-#[async_trait(?Send)]
 impl<'a, S> Middleware<'a, S> for () {
     #[allow(clippy::trivially_copy_pass_by_ref)]
     #[inline]
-    async fn handle(&'a self, _ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
-        next.await
+    fn handle(
+        &'a self,
+        _ctx: &'a mut Context<S>,
+        next: Next<'a>,
+    ) -> impl 'a + Future<Output = Result> {
+        next
     }
 }
 
 /// ok endpoint, always return Ok(())
-#[async_trait(?Send)]
 impl<'a, S> Endpoint<'a, S> for () {
     #[allow(clippy::trivially_copy_pass_by_ref)]
     #[inline]
@@ -198,7 +221,6 @@ impl<'a, S> Endpoint<'a, S> for () {
 }
 
 /// status endpoint.
-#[async_trait(?Send)]
 impl<'a, S> Endpoint<'a, S> for Status {
     #[inline]
     async fn call(&'a self, _ctx: &'a mut Context<S>) -> Result {
@@ -207,7 +229,6 @@ impl<'a, S> Endpoint<'a, S> for Status {
 }
 
 /// String endpoint.
-#[async_trait(?Send)]
 impl<'a, S> Endpoint<'a, S> for String {
     #[inline]
     #[allow(clippy::ptr_arg)]
@@ -218,7 +239,6 @@ impl<'a, S> Endpoint<'a, S> for String {
 }
 
 /// Static slice endpoint.
-#[async_trait(?Send)]
 impl<'a, S> Endpoint<'a, S> for &'static str {
     #[inline]
     async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
@@ -228,7 +248,6 @@ impl<'a, S> Endpoint<'a, S> for &'static str {
 }
 
 /// Redirect endpoint.
-#[async_trait(?Send)]
 impl<'a, S> Endpoint<'a, S> for Uri {
     #[inline]
     async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
@@ -0,0 +1,190 @@
+//! This module provides predicates over context, used by conditional middlewares.
+//!
+//! ### Example
+//! ```rust
+//! use roa_core::predicate::{header, path_prefix};
+//! use roa_core::{App, Context, MiddlewareExt, Next, Result};
+//! use roa_core::http::header::AUTHORIZATION;
+//!
+//! async fn auth(ctx: &mut Context, next: Next<'_>) -> Result {
+//!     next.await
+//! }
+//!
+//! async fn end(ctx: &mut Context) -> Result {
+//!     Ok(())
+//! }
+//!
+//! let app = App::new()
+//!     .gate(auth.unless(path_prefix(["/health"])))
+//!     .gate(auth.when(header(AUTHORIZATION)))
+//!     .end(end);
+//! ```
+
+use http::header::HeaderName;
+use http::Method;
+
+use crate::Context;
+
+/// A predicate over context, deciding whether a conditional middleware is applied.
+///
+/// It's implemented for `Fn(&Context<S>) -> bool`.
+pub trait Predicate<S = ()>: 'static {
+    /// Test context.
+    fn test(&self, ctx: &Context<S>) -> bool;
+}
+
+impl<S, F> Predicate<S> for F
+where
+    F: 'static + Fn(&Context<S>) -> bool,
+{
+    #[inline]
+    fn test(&self, ctx: &Context<S>) -> bool {
+        (self)(ctx)
+    }
+}
+
+/// A predicate negating another one, constructed by `not`.
+pub struct Not<P>(P);
+
+/// A predicate matching path prefixes, constructed by `path_prefix`.
+pub struct PathPrefix(Vec<String>);
+
+/// A predicate matching methods, constructed by `methods`.
+pub struct Methods(Vec<Method>);
+
+/// A predicate matching a header, constructed by `header` or `header_value`.
+pub struct Header {
+    name: HeaderName,
+    value: Option<String>,
+}
+
+/// Negate a predicate.
+#[inline]
+pub fn not<P>(predicate: P) -> Not<P> {
+    Not(predicate)
+}
+
+/// Match any of the path prefixes.
+///
+/// Prefixes are matched by segments, `/api` matches `/api` and `/api/user`,
+/// but not `/apis`.
+pub fn path_prefix<I>(prefixes: I) -> PathPrefix
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    PathPrefix(
+        prefixes
+            .into_iter()
+            .map(|prefix| prefix.into().trim_end_matches('/').to_string())
+            .collect(),
+    )
+}
+
+/// Match any of the methods.
+pub fn methods<I>(methods: I) -> Methods
+where
+    I: IntoIterator<Item = Method>,
+{
+    Methods(methods.into_iter().collect())
+}
+
+/// Match requests with the header.
+#[inline]
+pub fn header(name: HeaderName) -> Header {
+    Header { name, value: None }
+}
+
+/// Match requests with the header equal to the value.
+#[inline]
+pub fn header_value(name: HeaderName, value: impl Into<String>) -> Header {
+    Header {
+        name,
+        value: Some(value.into()),
+    }
+}
+
+impl<S, P> Predicate<S> for Not<P>
+where
+    P: Predicate<S>,
+{
+    #[inline]
+    fn test(&self, ctx: &Context<S>) -> bool {
+        !self.0.test(ctx)
+    }
+}
+
+impl<S> Predicate<S> for PathPrefix {
+    fn test(&self, ctx: &Context<S>) -> bool {
+        let path = ctx.uri().path();
+        self.0
+            .iter()
+            .any(|prefix| match path.strip_prefix(prefix.as_str()) {
+                Some(rest) => rest.is_empty() || rest.starts_with('/'),
+                None => false,
+            })
+    }
+}
+
+impl<S> Predicate<S> for Methods {
+    #[inline]
+    fn test(&self, ctx: &Context<S>) -> bool {
+        self.0.contains(ctx.method())
+    }
+}
+
+impl<S> Predicate<S> for Header {
+    fn test(&self, ctx: &Context<S>) -> bool {
+        let mut values = ctx.req.headers.get_all(&self.name).iter();
+        match &self.value {
+            None => values.next().is_some(),
+            Some(expected) => values.any(|value| value.as_bytes() == expected.as_bytes()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod tests {
+    use http::header::CONTENT_TYPE;
+    use http::{Method, StatusCode};
+
+    use super::{header, header_value, methods, not, path_prefix, Predicate};
+    use crate::{App, Context, Request, Result};
+
+    #[tokio::test]
+    async fn prefix() {
+        async fn test(ctx: &mut Context) -> Result {
+            let predicate = path_prefix(["/api/", "/assets"]);
+            let matched = ["/api", "/api/user?id=1", "/assets/a.js"];
+            let expected = matched.contains(&ctx.req.uri.to_string().as_str());
+            assert_eq!(expected, predicate.test(ctx));
+            assert!(path_prefix(["/"]).test(ctx));
+            Ok(())
+        }
+        let service = App::new().end(test).http_service();
+        for path in ["/api", "/api/user?id=1", "/assets/a.js", "/apis", "/"] {
+            let mut req = Request::default();
+            req.uri = path.parse().unwrap();
+            assert_eq!(StatusCode::OK, service.clone().serve(req).await.status);
+        }
+    }
+
+    #[tokio::test]
+    async fn method_and_header() {
+        async fn test(ctx: &mut Context) -> Result {
+            assert!(methods([Method::POST, Method::PUT]).test(ctx));
+            assert!(not(methods([Method::GET])).test(ctx));
+            assert!(header(CONTENT_TYPE).test(ctx));
+            assert!(header_value(CONTENT_TYPE, "application/json").test(ctx));
+            assert!(!header_value(CONTENT_TYPE, "text/plain").test(ctx));
+            assert!((|ctx: &Context| ctx.method() == Method::POST).test(ctx));
+            Ok(())
+        }
+        let mut req = Request::default();
+        req.method = Method::POST;
+        req.headers
+            .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+        let resp = App::new().end(test).http_service().serve(req).await;
+        assert_eq!(StatusCode::OK, resp.status);
+    }
+}
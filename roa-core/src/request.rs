@@ -1,11 +1,19 @@
+mod limit;
+
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use bytes::Bytes;
 use futures::stream::{Stream, TryStreamExt};
+use http::header::CONTENT_LENGTH;
 use http::{Extensions, HeaderMap, HeaderValue, Method, Uri, Version};
 use hyper::Body;
+use limit::Limited;
+pub use limit::PayloadTooLarge;
 use tokio::io::AsyncRead;
 use tokio_util::io::StreamReader;
+
 /// Http request type of roa.
 pub struct Request {
     /// The request's method
@@ -23,6 +31,10 @@ pub struct Request {
     extensions: Extensions,
 
     body: Body,
+
+    body_limit: Option<u64>,
+
+    limit_exceeded: Arc<AtomicBool>,
 }
 
 impl Request {
@@ -49,12 +61,43 @@ impl Request {
     }
     /// Get body as Stream.
     /// This method will consume inner body.
+    ///
+    /// If a body limit is set, the stream fails with `PayloadTooLarge`
+    /// at once when `Content-Length` exceeds the limit,
+    /// or when the received body goes over the limit.
     #[inline]
     pub fn stream(
         &mut self,
     ) -> impl Stream<Item = io::Result<Bytes>> + Sync + Send + Unpin + 'static {
-        self.raw_body()
-            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+        let limit = self.body_limit.unwrap_or(u64::MAX);
+        let hint = self
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        let stream = self
+            .raw_body()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+        Limited::new(stream, limit, hint, self.limit_exceeded.clone())
+    }
+
+    /// Get the body limit in bytes.
+    #[inline]
+    pub fn body_limit(&self) -> Option<u64> {
+        self.body_limit
+    }
+
+    /// Set the body limit in bytes, `None` means no limit.
+    /// It takes effect on `Request::stream` and `Request::reader`, not on the raw body.
+    #[inline]
+    pub fn set_body_limit(&mut self, limit: Option<u64>) {
+        self.body_limit = limit;
+    }
+
+    /// Whether the body has exceeded the limit while reading.
+    #[inline]
+    pub fn body_limit_exceeded(&self) -> bool {
+        self.limit_exceeded.load(Ordering::Acquire)
     }
 
     /// Get body as AsyncRead.
@@ -76,6 +119,8 @@ impl From<http::Request<Body>> for Request {
             headers: parts.headers,
             extensions: parts.extensions,
             body,
+            body_limit: None,
+            limit_exceeded: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -111,4 +156,59 @@ mod tests {
         assert_eq!(StatusCode::OK, resp.status);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn body_limit() -> Result<(), Box<dyn std::error::Error>> {
+        async fn read(ctx: &mut Context) -> Result<(), Status> {
+            let mut data = String::new();
+            ctx.req.reader().read_to_string(&mut data).await?;
+            Ok(())
+        }
+        let service = App::new().body_limit(5).end(read).http_service();
+        let req = Request::from(http::Request::new(Body::from("Hello")));
+        let resp = service.clone().serve(req).await;
+        assert_eq!(StatusCode::OK, resp.status);
+
+        // chunked
+        let stream = futures::stream::iter(vec![Ok::<_, std::io::Error>("Hello"), Ok(", World!")]);
+        let req = Request::from(http::Request::new(Body::wrap_stream(stream)));
+        let resp = service.clone().serve(req).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, resp.status);
+
+        // content-length
+        let req = http::Request::builder()
+            .header(http::header::CONTENT_LENGTH, "13")
+            .body(Body::from("Hello, World!"))?;
+        let resp = service.serve(Request::from(req)).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, resp.status);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn body_limit_source() -> Result<(), Box<dyn std::error::Error>> {
+        async fn read(ctx: &mut Context) -> Result<(), Status> {
+            let mut data = String::new();
+            let result = ctx.req.reader().read_to_string(&mut data).await;
+            if ctx.uri().path() == "/unavailable" {
+                return Err(Status::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "database is down",
+                    true,
+                ));
+            }
+            let err = result.unwrap_err();
+            Err(Status::new(StatusCode::INTERNAL_SERVER_ERROR, "wrapped", false).with_source(err))
+        }
+        let service = App::new().body_limit(5).end(read).http_service();
+        let request = |path: &str| {
+            let mut req = Request::from(http::Request::new(Body::from("Hello, World!")));
+            req.uri = path.parse().unwrap();
+            req
+        };
+        let resp = service.clone().serve(request("/wrapped")).await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, resp.status);
+        let resp = service.serve(request("/unavailable")).await;
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, resp.status);
+        Ok(())
+    }
 }
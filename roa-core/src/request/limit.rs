@@ -0,0 +1,96 @@
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::Stream;
+
+/// An error occurs when request body exceeds the limit.
+///
+/// It will be converted to a `Status` with 413 PAYLOAD TOO LARGE.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PayloadTooLarge {
+    /// The limit of body size in bytes.
+    pub limit: u64,
+}
+
+impl Display for PayloadTooLarge {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "payload too large, the limit is {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for PayloadTooLarge {}
+
+impl From<PayloadTooLarge> for io::Error {
+    #[inline]
+    fn from(err: PayloadTooLarge) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// A stream stopping with `PayloadTooLarge` once the limit is exceeded.
+pub struct Limited<S> {
+    inner: S,
+    limit: u64,
+    remaining: u64,
+    oversized: bool,
+    exceeded: Arc<AtomicBool>,
+    done: bool,
+}
+
+impl<S> Limited<S> {
+    /// Construct a limited stream.
+    /// Fail at the first poll if the content length hint exceeds the limit.
+    #[inline]
+    pub fn new(inner: S, limit: u64, hint: Option<u64>, exceeded: Arc<AtomicBool>) -> Self {
+        Self {
+            inner,
+            limit,
+            remaining: limit,
+            oversized: matches!(hint, Some(len) if len > limit),
+            exceeded,
+            done: false,
+        }
+    }
+
+    #[inline]
+    fn exceed(&mut self) -> Poll<Option<io::Result<Bytes>>> {
+        self.exceeded.store(true, Ordering::Release);
+        self.done = true;
+        let limit = self.limit;
+        Poll::Ready(Some(Err(PayloadTooLarge { limit }.into())))
+    }
+}
+
+impl<S> Stream for Limited<S>
+where
+    S: Stream<Item = io::Result<Bytes>> + Unpin,
+{
+    type Item = io::Result<Bytes>;
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        if self.oversized {
+            return self.exceed();
+        }
+        match futures::ready!(Pin::new(&mut self.inner).poll_next(cx)) {
+            Some(Ok(data)) => {
+                let len = data.len() as u64;
+                if len > self.remaining {
+                    self.exceed()
+                } else {
+                    self.remaining -= len;
+                    Poll::Ready(Some(Ok(data)))
+                }
+            }
+            item => Poll::Ready(item),
+        }
+    }
+}
@@ -31,8 +31,9 @@ impl Response {
         }
     }
 
+    /// Convert into a http response, the body keeps its exact length and trailers.
     #[inline]
-    fn into_resp(self) -> http::Response<hyper::Body> {
+    fn into_resp(self) -> http::Response<Body> {
         let (mut parts, _) = http::Response::new(()).into_parts();
         let Response {
             status,
@@ -43,7 +44,7 @@ impl Response {
         parts.status = status;
         parts.version = version;
         parts.headers = headers;
-        http::Response::from_parts(parts, body.into())
+        http::Response::from_parts(parts, body)
     }
 }
 
@@ -62,13 +63,21 @@ impl DerefMut for Response {
     }
 }
 
-impl From<Response> for http::Response<hyper::Body> {
+impl From<Response> for http::Response<Body> {
     #[inline]
     fn from(value: Response) -> Self {
         value.into_resp()
     }
 }
 
+/// Trailers are dropped in this conversion, use `http::Response<Body>` to keep them.
+impl From<Response> for http::Response<hyper::Body> {
+    #[inline]
+    fn from(value: Response) -> Self {
+        value.into_resp().map(Into::into)
+    }
+}
+
 impl Default for Response {
     #[inline]
     fn default() -> Self {
@@ -25,6 +25,9 @@
 ///     Ok(())
 /// }
 /// ```
-pub trait State: 'static + Clone + Send + Sync + Sized {}
+///
+/// The state must be `Send + Sync` to be served by multi-threaded runtimes,
+/// but it can hold `Rc` or `RefCell` when the app is served thread-per-core by `App::serve_local`.
+pub trait State: 'static + Clone + Sized {}
 
-impl<T: 'static + Clone + Send + Sync + Sized> State for T {}
+impl<T: 'static + Clone + Sized> State for T {}
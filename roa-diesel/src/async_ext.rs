@@ -3,11 +3,15 @@ use diesel::helper_types::Limit;
 use diesel::query_dsl::methods::{ExecuteDsl, LimitDsl, LoadQuery};
 use diesel::query_dsl::RunQueryDsl;
 use diesel::result::{Error as DieselError, OptionalExtension};
-use roa::{async_trait, Context, Result, State};
+use roa::http::StatusCode;
+use roa::{async_trait, Context, Result, State, Status};
 
 use crate::pool::{AsyncPool, Pool};
 
 /// A context extension to execute diesel dsl asynchronously.
+///
+/// Queries are skipped if the request is cancelled before they start,
+/// see `Context::cancellation_token`.
 #[async_trait]
 pub trait SqlQuery<Conn: 'static + Connection> {
     /// Executes the given command, returning the number of rows affected.
@@ -84,10 +88,26 @@ pub trait SqlQuery<Conn: 'static + Connection> {
         Limit<Q>: LoadQuery<Conn, U>;
 }
 
+/// Run a blocking query, skipped if the request is cancelled before it starts.
+#[inline]
+async fn run_blocking<S, T>(
+    ctx: &Context<S>,
+    query: impl 'static + Send + FnOnce() -> T,
+) -> Result<T>
+where
+    T: 'static + Send,
+{
+    let token = ctx.cancellation_token().clone();
+    ctx.exec
+        .spawn_blocking(move || (!token.is_cancelled()).then(query))
+        .await?
+        .ok_or_else(|| Status::new(StatusCode::SERVICE_UNAVAILABLE, "request cancelled", false))
+}
+
 #[async_trait]
 impl<S, Conn> SqlQuery<Conn> for Context<S>
 where
-    S: State + AsRef<Pool<Conn>>,
+    S: State + Send + Sync + AsRef<Pool<Conn>>,
     Conn: 'static + Connection,
 {
     #[inline]
@@ -96,10 +116,7 @@ where
         E: 'static + Send + ExecuteDsl<Conn>,
     {
         let conn = self.get_conn().await?;
-        Ok(self
-            .exec
-            .spawn_blocking(move || ExecuteDsl::<Conn>::execute(exec, &*conn))
-            .await?)
+        Ok(run_blocking(self, move || ExecuteDsl::<Conn>::execute(exec, &*conn)).await??)
     }
 
     /// Executes the given query, returning a `Vec` with the returned rows.
@@ -127,7 +144,7 @@ where
         Q: 'static + Send + LoadQuery<Conn, U>,
     {
         let conn = self.get_conn().await?;
-        match self.exec.spawn_blocking(move || query.load(&*conn)).await {
+        match run_blocking(self, move || query.load(&*conn)).await? {
             Ok(data) => Ok(data),
             Err(DieselError::NotFound) => Ok(Vec::new()),
             Err(err) => Err(err.into()),
@@ -150,10 +167,8 @@ where
         Q: 'static + Send + LoadQuery<Conn, U>,
     {
         let conn = self.get_conn().await?;
-        Ok(self
-            .exec
-            .spawn_blocking(move || query.get_result(&*conn))
-            .await
+        Ok(run_blocking(self, move || query.get_result(&*conn))
+            .await?
             .optional()?)
     }
 
@@ -188,10 +203,10 @@ where
         Limit<Q>: LoadQuery<Conn, U>,
     {
         let conn = self.get_conn().await?;
-        Ok(self
-            .exec
-            .spawn_blocking(move || query.limit(1).get_result(&*conn))
-            .await
-            .optional()?)
+        Ok(
+            run_blocking(self, move || query.limit(1).get_result(&*conn))
+                .await?
+                .optional()?,
+        )
     }
 }
@@ -88,13 +88,13 @@ where
 #[async_trait]
 impl<S, Conn> AsyncPool<Conn> for Context<S>
 where
-    S: State + AsRef<Pool<Conn>>,
+    S: State + Send + Sync + AsRef<Pool<Conn>>,
     Conn: Connection + 'static,
 {
     #[inline]
     async fn get_conn(&self) -> Result<WrapConnection<Conn>, Status> {
         let pool = self.as_ref().clone();
-        Ok(self.exec.spawn_blocking(move || pool.get()).await?)
+        Ok(self.exec.spawn_blocking(move || pool.get()).await??)
     }
 
     #[inline]
@@ -103,12 +103,15 @@ where
         Ok(self
             .exec
             .spawn_blocking(move || pool.get_timeout(timeout))
-            .await?)
+            .await??)
     }
 
     #[inline]
     async fn pool_state(&self) -> r2d2::State {
         let pool = self.as_ref().clone();
-        self.exec.spawn_blocking(move || pool.state()).await
+        self.exec
+            .spawn_blocking(move || pool.state())
+            .await
+            .expect("fail to get pool state")
     }
 }
@@ -11,7 +11,7 @@ use std::ops::{Deref, DerefMut};
 use juniper::http::GraphQLRequest;
 use juniper::{GraphQLType, GraphQLTypeAsync, RootNode, ScalarValue};
 use roa::preload::*;
-use roa::{async_trait, Context, Endpoint, Result, State};
+use roa::{Context, Endpoint, Result, State};
 
 /// A wrapper for `roa_core::SyncContext`.
 /// As an implementation of `juniper::Context`.
@@ -43,11 +43,10 @@ where
     SubscriptionT: GraphQLType<Sca>,
     Sca: ScalarValue;
 
-#[async_trait(?Send)]
 impl<'a, S, QueryT, MutationT, SubscriptionT, Sca> Endpoint<'a, S>
     for GraphQL<QueryT, MutationT, SubscriptionT, Sca>
 where
-    S: State,
+    S: State + Send + Sync,
     QueryT: GraphQLTypeAsync<Sca, Context = JuniperContext<S>> + Send + Sync + 'static,
     QueryT::TypeInfo: Send + Sync,
     MutationT: GraphQLTypeAsync<Sca, Context = QueryT::Context> + Send + Sync + 'static,
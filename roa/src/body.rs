@@ -88,7 +88,7 @@ use bytes::Bytes;
 use headers::{ContentLength, ContentType, HeaderMapExt};
 use tokio::io::{AsyncRead, AsyncReadExt};
 
-use crate::{async_trait, Context, Result, State};
+use crate::{async_trait, Context, Middleware, Next, Result, State};
 #[cfg(feature = "file")]
 mod file;
 #[cfg(feature = "file")]
@@ -102,8 +102,55 @@ use serde::de::DeserializeOwned;
 #[cfg(feature = "json")]
 use serde::Serialize;
 
+/// The max capacity preallocated by `PowerBody::read`,
+/// to avoid allocating by an untrusted `Content-Length`.
+const MAX_PREALLOCATION: u64 = 64 * 1024;
+
+/// A middleware to set the limit of request body size in bytes,
+/// overriding the app-wide `App::body_limit`.
+///
+/// Reading a body over the limit fails, and 413 PAYLOAD TOO LARGE will be responded.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa::body::{BodyLimit, PowerBody};
+/// use roa::router::{Router, post};
+/// use roa::{App, Context, MiddlewareExt};
+///
+/// async fn upload(ctx: &mut Context) -> roa::Result {
+///     let data = ctx.read().await?;
+///     Ok(())
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let router = Router::new()
+///     .on("/avatar", BodyLimit::new(1024 * 1024).end(post(upload)));
+/// let app = App::new().body_limit(4096).end(router.routes("/")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit(u64);
+
+impl BodyLimit {
+    /// Construct a body limit middleware.
+    #[inline]
+    pub fn new(limit: u64) -> Self {
+        Self(limit)
+    }
+}
+
+impl<'a, S> Middleware<'a, S> for BodyLimit {
+    #[inline]
+    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
+        ctx.req.set_body_limit(Some(self.0));
+        next.await
+    }
+}
+
 /// A context extension to read/write body more simply.
-#[async_trait]
+#[async_trait(?Send)]
 pub trait PowerBody {
     /// read request body as Bytes.
     async fn read(&mut self) -> Result<Vec<u8>>;
@@ -159,12 +206,15 @@ pub trait PowerBody {
         P: Send + AsRef<std::path::Path>;
 }
 
-#[async_trait]
+#[async_trait(?Send)]
 impl<S: State> PowerBody for Context<S> {
     #[inline]
     async fn read(&mut self) -> Result<Vec<u8>> {
         let mut data = match self.req.headers.typed_get::<ContentLength>() {
-            Some(hint) => Vec::with_capacity(hint.0 as usize),
+            Some(hint) => {
+                let limit = self.req.body_limit().unwrap_or(u64::MAX);
+                Vec::with_capacity(hint.0.min(limit).min(MAX_PREALLOCATION) as usize)
+            }
             None => Vec::new(),
         };
         self.req.reader().read_to_end(&mut data).await?;
@@ -397,6 +447,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn body_limit() -> Result<(), Box<dyn Error>> {
+        use super::BodyLimit;
+        use crate::test::TestClient;
+        use crate::MiddlewareExt;
+
+        async fn test(ctx: &mut Context) -> crate::Result {
+            ctx.read().await?;
+            Ok(())
+        }
+
+        let app = App::new().body_limit(4).end(BodyLimit::new(8).end(test));
+        let client = TestClient::new(app);
+        let resp = client.post("/").text("Hello").send().await;
+        assert_eq!(StatusCode::OK, resp.status);
+
+        // declared Content-Length exceeds limit
+        let resp = client
+            .post("/")
+            .header(crate::http::header::CONTENT_LENGTH, "1073741824")
+            .text("Hello")
+            .send()
+            .await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, resp.status);
+
+        let resp = client.post("/").text("Hello, World!").send().await;
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, resp.status);
+        assert_eq!(
+            "payload too large, the limit is 8 bytes",
+            resp.text().await?
+        );
+        Ok(())
+    }
+
     #[cfg(feature = "multipart")]
     mod multipart {
         use std::error::Error as StdError;
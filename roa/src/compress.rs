@@ -23,11 +23,11 @@
 
 use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder, ZstdEncoder};
 pub use async_compression::Level;
-use tokio_util::io::StreamReader;
+use tokio_util::io::{ReaderStream, StreamReader};
 
 use crate::http::header::{HeaderMap, ACCEPT_ENCODING, CONTENT_ENCODING};
 use crate::http::{HeaderValue, StatusCode};
-use crate::{async_trait, status, Context, Middleware, Next, Result};
+use crate::{status, Context, Middleware, Next, Result};
 
 /// A middleware to negotiate with client and compress response body automatically,
 /// supports gzip, deflate, brotli, zstd and identity.
@@ -139,7 +139,6 @@ impl Default for Compress {
     }
 }
 
-#[async_trait(?Send)]
 impl<'a, S> Middleware<'a, S> for Compress {
     #[allow(clippy::trivially_copy_pass_by_ref)]
     #[inline]
@@ -147,32 +146,32 @@ impl<'a, S> Middleware<'a, S> for Compress {
         next.await?;
         let level = self.0;
         let best_encoding = select_encoding(&ctx.req.headers)?;
-        let body = std::mem::take(&mut ctx.resp.body);
         let content_encoding = match best_encoding {
             None | Some(Encoding::Gzip) => {
-                ctx.resp
-                    .write_reader(GzipEncoder::with_quality(StreamReader::new(body), level));
+                ctx.resp.body.encode_stream(|body| {
+                    ReaderStream::new(GzipEncoder::with_quality(StreamReader::new(body), level))
+                });
                 Encoding::Gzip.to_header_value()
             }
             Some(Encoding::Deflate) => {
-                ctx.resp
-                    .write_reader(ZlibEncoder::with_quality(StreamReader::new(body), level));
+                ctx.resp.body.encode_stream(|body| {
+                    ReaderStream::new(ZlibEncoder::with_quality(StreamReader::new(body), level))
+                });
                 Encoding::Deflate.to_header_value()
             }
             Some(Encoding::Brotli) => {
-                ctx.resp
-                    .write_reader(BrotliEncoder::with_quality(StreamReader::new(body), level));
+                ctx.resp.body.encode_stream(|body| {
+                    ReaderStream::new(BrotliEncoder::with_quality(StreamReader::new(body), level))
+                });
                 Encoding::Brotli.to_header_value()
             }
             Some(Encoding::Zstd) => {
-                ctx.resp
-                    .write_reader(ZstdEncoder::with_quality(StreamReader::new(body), level));
+                ctx.resp.body.encode_stream(|body| {
+                    ReaderStream::new(ZstdEncoder::with_quality(StreamReader::new(body), level))
+                });
                 Encoding::Zstd.to_header_value()
             }
-            Some(Encoding::Identity) => {
-                ctx.resp.body = body;
-                Encoding::Identity.to_header_value()
-            }
+            Some(Encoding::Identity) => Encoding::Identity.to_header_value(),
         };
         ctx.resp.headers.append(CONTENT_ENCODING, content_encoding);
         Ok(())
@@ -187,14 +186,16 @@ mod tests {
 
     use bytes::Bytes;
     use futures::Stream;
+    use hyper::body::HttpBody;
     use tokio::task::spawn;
 
     use crate::body::DispositionType::*;
     use crate::compress::{Compress, Level};
-    use crate::http::header::ACCEPT_ENCODING;
-    use crate::http::StatusCode;
+    use crate::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+    use crate::http::{HeaderMap, StatusCode};
     use crate::preload::*;
-    use crate::{async_trait, App, Context, Middleware, Next};
+    use crate::test::TestClient;
+    use crate::{App, Context, Middleware, Next};
 
     struct Consumer<S> {
         counter: usize,
@@ -226,7 +227,6 @@ mod tests {
 
     struct Assert(usize);
 
-    #[async_trait(?Send)]
     impl<'a, S> Middleware<'a, S> for Assert {
         async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> crate::Result {
             next.await?;
@@ -244,6 +244,32 @@ mod tests {
         ctx.write_file("../assets/welcome.html", Inline).await
     }
 
+    #[tokio::test]
+    async fn keep_trailers() -> Result<(), Box<dyn std::error::Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", "0".parse()?);
+            ctx.resp.write("Hello, World").set_trailers(trailers);
+            Ok(())
+        }
+        let client = TestClient::new(App::new().gate(Compress::default()).end(end));
+        for (encoding, len) in [("gzip", None), ("identity", Some(12))] {
+            let mut resp = client
+                .get("/")
+                .header(ACCEPT_ENCODING, encoding)
+                .send()
+                .await;
+            resp.assert_header(CONTENT_ENCODING, encoding);
+            assert_eq!(len, resp.body.exact_len());
+            while let Some(data) = resp.body.data().await {
+                data?;
+            }
+            let trailers = resp.body.trailers().await?.expect("trailers are lost");
+            assert_eq!("0", trailers["grpc-status"]);
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn compress() -> Result<(), Box<dyn std::error::Error>> {
         let app = App::new()
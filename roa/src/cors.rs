@@ -15,7 +15,7 @@ use roa_core::Status;
 
 use crate::http::header::{HeaderName, HeaderValue, ORIGIN, VARY};
 use crate::http::{Method, StatusCode};
-use crate::{async_trait, Context, Middleware, Next, Result};
+use crate::{Context, Middleware, Next, Result};
 
 /// A middleware to deal with Cross-Origin Resource Sharing (CORS).
 ///
@@ -235,7 +235,6 @@ impl Builder {
     }
 }
 
-#[async_trait(?Send)]
 impl<'a, S> Middleware<'a, S> for Cors {
     #[inline]
     async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
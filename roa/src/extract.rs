@@ -0,0 +1,464 @@
+//! This module provides a trait `FromContext` and an endpoint wrapper `extract`,
+//! to write endpoints taking typed arguments extracted from context.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::extract::{extract, Json, Query};
+//! use roa::preload::*;
+//! use roa::http::StatusCode;
+//! use roa::test::TestClient;
+//! use roa::{App, Context};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Options {
+//!     greeting: String,
+//! }
+//!
+//! async fn greet(ctx: &mut Context, Json(user): Json<User>, Query(opts): Query<Options>) -> roa::Result {
+//!     ctx.write(format!("{}, {}!", opts.greeting, user.name));
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = TestClient::new(App::new().end(extract(greet)));
+//!     let resp = client
+//!         .post("/?greeting=Hello")
+//!         .body("application/json", r#"{"name": "Hexilee"}"#)
+//!         .send()
+//!         .await;
+//!     assert_eq!("Hello, Hexilee!", resp.text().await?);
+//!
+//!     // missing query
+//!     let resp = client
+//!         .post("/")
+//!         .body("application/json", r#"{"name": "Hexilee"}"#)
+//!         .send()
+//!         .await;
+//!     assert_eq!(StatusCode::BAD_REQUEST, resp.status);
+//!     Ok(())
+//! }
+//! ```
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use headers::{Header, HeaderMapExt};
+#[cfg(any(feature = "json", feature = "urlencoded", feature = "router"))]
+use serde::de::DeserializeOwned;
+
+use crate::http::StatusCode;
+#[cfg(feature = "router")]
+use crate::router::RouterParam;
+use crate::{async_trait, status, Context, Endpoint, Result, State as AppState};
+
+/// A trait to extract a value from context.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa::extract::FromContext;
+/// use roa::{async_trait, Context, Result};
+///
+/// struct ClientIp(std::net::IpAddr);
+///
+/// #[async_trait(?Send)]
+/// impl<S> FromContext<S> for ClientIp {
+///     async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+///         Ok(ClientIp(ctx.remote_addr.ip()))
+///     }
+/// }
+/// ```
+#[async_trait(?Send)]
+pub trait FromContext<S = ()>: Sized {
+    /// Extract a value from context.
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self>;
+}
+
+/// An endpoint wrapper of function taking extractor arguments,
+/// constructed by `extract`.
+pub struct Extract<F, Args> {
+    handler: F,
+    _args: PhantomData<fn() -> Args>,
+}
+
+/// Wrap a function with signature `async fn(&mut Context<S>, T1, T2, ...) -> Result`
+/// as an endpoint, where each of `T1, T2, ...` implements `FromContext<S>`.
+///
+/// Arguments are extracted in order, the first failure is thrown.
+pub fn extract<F, Args>(handler: F) -> Extract<F, Args> {
+    Extract {
+        handler,
+        _args: PhantomData,
+    }
+}
+
+macro_rules! impl_extract {
+    ($($arg:ident),+) => {
+        impl<'a, S, F, Fut, $($arg),+> Endpoint<'a, S> for Extract<F, ($($arg,)+)>
+        where
+            S: AppState,
+            F: 'static + Send + Sync + Fn(&'a mut Context<S>, $($arg),+) -> Fut,
+            Fut: 'a + Future<Output = Result>,
+            $($arg: 'static + FromContext<S>),+
+        {
+            #[allow(non_snake_case)]
+            #[inline]
+            async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
+                $(let $arg = $arg::from_context(ctx).await?;)+
+                (self.handler)(ctx, $($arg),+).await
+            }
+        }
+    };
+}
+
+impl_extract!(T1);
+impl_extract!(T1, T2);
+impl_extract!(T1, T2, T3);
+impl_extract!(T1, T2, T3, T4);
+impl_extract!(T1, T2, T3, T4, T5);
+impl_extract!(T1, T2, T3, T4, T5, T6);
+
+macro_rules! impl_wrapper {
+    ($name:ident) => {
+        impl<T> Deref for $name<T> {
+            type Target = T;
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+    };
+}
+
+/// Deserialize request body as json.
+///
+/// Throw 415 UNSUPPORTED MEDIA TYPE if the content type is not json,
+/// or 400 BAD REQUEST if the body is invalid.
+#[cfg(feature = "json")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+/// Deserialize request body as x-www-form-urlencoded.
+///
+/// Throw 415 UNSUPPORTED MEDIA TYPE if the content type is not x-www-form-urlencoded,
+/// or 400 BAD REQUEST if the body is invalid.
+#[cfg(feature = "urlencoded")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "urlencoded")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Form<T>(pub T);
+
+/// Deserialize router parameters, must be used in `Router`.
+///
+/// Throw 400 BAD REQUEST if parameters are invalid.
+#[cfg(feature = "router")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "router")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Path<T>(pub T);
+
+/// Deserialize query string.
+///
+/// Throw 400 BAD REQUEST if the query is invalid.
+#[cfg(feature = "urlencoded")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "urlencoded")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+/// Decode a typed header.
+///
+/// Throw 400 BAD REQUEST if the header is missing or invalid.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TypedHeader<H>(pub H);
+
+/// Clone the state of app.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct State<S>(pub S);
+
+#[cfg(feature = "json")]
+impl_wrapper!(Json);
+#[cfg(feature = "urlencoded")]
+impl_wrapper!(Form);
+#[cfg(feature = "router")]
+impl_wrapper!(Path);
+#[cfg(feature = "urlencoded")]
+impl_wrapper!(Query);
+impl_wrapper!(TypedHeader);
+impl_wrapper!(State);
+
+/// Check the essence of content type.
+#[cfg(any(feature = "json", feature = "urlencoded"))]
+fn check_content_type<S>(ctx: &Context<S>, expected: impl Fn(&mime::Mime) -> bool) -> Result {
+    let content_type: Option<mime::Mime> = ctx
+        .req
+        .headers
+        .typed_get::<headers::ContentType>()
+        .map(Into::into);
+    match content_type {
+        Some(ref typ) if expected(typ) => Ok(()),
+        Some(typ) => Err(status!(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("content type `{}` is not supported", typ)
+        )),
+        None => Err(status!(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "content type is required"
+        )),
+    }
+}
+
+#[cfg(feature = "json")]
+#[async_trait(?Send)]
+impl<S, T> FromContext<S> for Json<T>
+where
+    S: AppState,
+    T: DeserializeOwned,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        use crate::body::PowerBody;
+        check_content_type(ctx, |typ| {
+            typ.subtype() == mime::JSON || typ.suffix() == Some(mime::JSON)
+        })?;
+        Ok(Json(ctx.read_json().await?))
+    }
+}
+
+#[cfg(feature = "urlencoded")]
+#[async_trait(?Send)]
+impl<S, T> FromContext<S> for Form<T>
+where
+    S: AppState,
+    T: DeserializeOwned,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        use crate::body::PowerBody;
+        check_content_type(ctx, |typ| {
+            typ.essence_str() == mime::APPLICATION_WWW_FORM_URLENCODED.essence_str()
+        })?;
+        Ok(Form(ctx.read_form().await?))
+    }
+}
+
+#[cfg(feature = "router")]
+#[async_trait(?Send)]
+impl<S, T> FromContext<S> for Path<T>
+where
+    T: DeserializeOwned,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        ctx.params().map(Path)
+    }
+}
+
+#[cfg(feature = "urlencoded")]
+#[async_trait(?Send)]
+impl<S, T> FromContext<S> for Query<T>
+where
+    T: DeserializeOwned,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        serde_urlencoded::from_str(ctx.uri().query().unwrap_or_default())
+            .map(Query)
+            .map_err(|err| status!(StatusCode::BAD_REQUEST, format!("invalid query: {}", err)))
+    }
+}
+
+#[async_trait(?Send)]
+impl<S, H> FromContext<S> for TypedHeader<H>
+where
+    H: Header,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        let name = H::name();
+        if !ctx.req.headers.contains_key(name) {
+            return Err(status!(
+                StatusCode::BAD_REQUEST,
+                format!("header `{}` is required", name)
+            ));
+        }
+        ctx.req
+            .headers
+            .typed_try_get::<H>()
+            .ok()
+            .flatten()
+            .map(TypedHeader)
+            .ok_or_else(|| {
+                status!(
+                    StatusCode::BAD_REQUEST,
+                    format!("header `{}` is invalid", name)
+                )
+            })
+    }
+}
+
+#[async_trait(?Send)]
+impl<S> FromContext<S> for State<S>
+where
+    S: Clone,
+{
+    #[inline]
+    async fn from_context(ctx: &mut Context<S>) -> Result<Self> {
+        Ok(State(S::clone(ctx)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use headers::UserAgent;
+
+    use super::{extract, State, TypedHeader};
+    use crate::http::header::USER_AGENT;
+    use crate::http::StatusCode;
+    use crate::test::TestClient;
+    use crate::{App, Context};
+
+    async fn user_agent(
+        ctx: &mut Context<u64>,
+        TypedHeader(agent): TypedHeader<UserAgent>,
+        State(id): State<u64>,
+    ) -> crate::Result {
+        ctx.resp.write(format!("{}: {}", id, agent));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn typed_header_and_state() -> Result<(), Box<dyn std::error::Error>> {
+        let client = TestClient::new(App::state(1).end(extract(user_agent)));
+        let resp = client.get("/").header(USER_AGENT, "roa").send().await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!("1: roa", resp.text().await?);
+
+        let resp = client.get("/").send().await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!("header `user-agent` is required", resp.text().await?);
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json() -> Result<(), Box<dyn std::error::Error>> {
+        use serde::Deserialize;
+
+        use super::Json;
+
+        #[derive(Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        async fn create(ctx: &mut Context, Json(user): Json<User>) -> crate::Result {
+            ctx.resp.write(user.name);
+            Ok(())
+        }
+
+        let client = TestClient::new(App::new().end(extract(create)));
+        let resp = client
+            .post("/")
+            .body("application/json", r#"{"name": "Hexilee"}"#)
+            .send()
+            .await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!("Hexilee", resp.text().await?);
+
+        let resp = client.post("/").text(r#"{"name": "Hexilee"}"#).send().await;
+        resp.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let resp = client.post("/").body("application/json", "{").send().await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[cfg(feature = "urlencoded")]
+    #[tokio::test]
+    async fn form_and_query() -> Result<(), Box<dyn std::error::Error>> {
+        use serde::Deserialize;
+
+        use super::{Form, Query};
+
+        #[derive(Deserialize)]
+        struct User {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Page {
+            page: u32,
+        }
+
+        async fn create(
+            ctx: &mut Context,
+            Form(user): Form<User>,
+            Query(page): Query<Page>,
+        ) -> crate::Result {
+            ctx.resp.write(format!("{}: {}", page.page, user.name));
+            Ok(())
+        }
+
+        let client = TestClient::new(App::new().end(extract(create)));
+        let resp = client
+            .post("/?page=1")
+            .body("application/x-www-form-urlencoded", "name=Hexilee")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!("1: Hexilee", resp.text().await?);
+
+        let resp = client
+            .post("/?page=one")
+            .body("application/x-www-form-urlencoded", "name=Hexilee")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[cfg(feature = "router")]
+    #[tokio::test]
+    async fn path() -> Result<(), Box<dyn std::error::Error>> {
+        use serde::Deserialize;
+
+        use super::Path;
+        use crate::router::Router;
+
+        #[derive(Deserialize)]
+        struct Params {
+            group: String,
+            id: u64,
+        }
+
+        async fn get(ctx: &mut Context, Path(params): Path<Params>) -> crate::Result {
+            ctx.resp.write(format!("{}/{}", params.group, params.id));
+            Ok(())
+        }
+
+        let router = Router::new().on("/:group/:id", extract(get));
+        let client = TestClient::new(App::new().end(router.routes("/")?));
+        let resp = client.get("/admin/1").send().await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!("admin/1", resp.text().await?);
+
+        let resp = client.get("/admin/x").send().await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
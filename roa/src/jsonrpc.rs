@@ -51,7 +51,7 @@ use bytes::Bytes;
 pub use jsonrpc_v2::*;
 
 use crate::body::PowerBody;
-use crate::{async_trait, Context, Endpoint, Result, State};
+use crate::{Context, Endpoint, Result, State};
 
 /// A wrapper for [`jsonrpc_v2::Server`], implemented [`roa::Endpoint`].
 ///
@@ -59,7 +59,6 @@ use crate::{async_trait, Context, Endpoint, Result, State};
 /// [`roa::Endpoint`]: https://docs.rs/roa/0.6.0/roa/trait.Endpoint.html
 pub struct RpcEndpoint<R>(pub Server<R>);
 
-#[async_trait(? Send)]
 impl<'a, S, R> Endpoint<'a, S> for RpcEndpoint<R>
 where
     S: State,
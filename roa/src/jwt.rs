@@ -77,7 +77,7 @@ use serde_json::Value;
 
 use crate::http::header::{HeaderValue, WWW_AUTHENTICATE};
 use crate::http::StatusCode;
-use crate::{async_trait, throw, Context, Middleware, Next, Result, Status};
+use crate::{throw, Context, Middleware, Next, Result, Status};
 
 /// A private scope.
 struct JwtScope;
@@ -169,7 +169,6 @@ impl JwtGuard {
     }
 }
 
-#[async_trait(? Send)]
 impl<'a, S> Middleware<'a, S> for JwtGuard {
     #[inline]
     async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
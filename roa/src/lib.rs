@@ -32,16 +32,25 @@ pub mod jwt;
 #[cfg_attr(feature = "docs", doc(cfg(feature = "compress")))]
 pub mod compress;
 
+#[cfg(feature = "json")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+pub mod problem;
+
 #[cfg(feature = "jsonrpc")]
 #[cfg_attr(feature = "docs", doc(cfg(feature = "jsonrpc")))]
 pub mod jsonrpc;
 
 pub mod body;
 pub mod cors;
+pub mod extract;
 pub mod forward;
 pub mod logger;
 pub mod query;
+pub mod request_id;
 pub mod stream;
+pub mod test;
+pub mod timeout;
+pub mod trace;
 
 /// Reexport all extension traits.
 pub mod preload {
@@ -32,18 +32,29 @@ use bytesize::ByteSize;
 use futures::task::{self, Poll};
 use futures::{Future, Stream};
 use roa_core::http::{Method, StatusCode};
-use tracing::{error, info};
+use tracing::{error, info, Span};
 
 use crate::http::Uri;
-use crate::{Context, Executor, JoinHandle, Next, Result};
+use crate::{Context, Executor, JoinHandle, Next, RequestId, Result};
 
 /// A finite-state machine to log success information in each successful response.
 enum StreamLogger<S> {
     /// Polling state, as a body stream.
-    Polling { stream: S, task: LogTask },
+    ///
+    /// `len` is the exact length of body, if it's known.
+    /// The server may stop polling once `len` bytes are written,
+    /// so logging starts before the last chunk is yielded.
+    Polling {
+        stream: S,
+        len: Option<u64>,
+        task: LogTask,
+    },
 
-    /// Logging state, as a logger future.
-    Logging(JoinHandle<()>),
+    /// Logging state, as a logger future, with the last chunk yielded after it.
+    Logging {
+        handler: JoinHandle<()>,
+        last: Option<Bytes>,
+    },
 
     /// Complete, as a empty stream.
     Complete,
@@ -56,8 +67,11 @@ struct LogTask {
     method: Method,
     status_code: StatusCode,
     uri: Uri,
+    request_id: Option<RequestId>,
     start: Instant,
     exec: Executor,
+    /// The span the middleware runs in, the body is polled out of it.
+    span: Span,
 }
 
 impl LogTask {
@@ -68,11 +82,17 @@ impl LogTask {
             method,
             status_code,
             uri,
+            request_id,
             start,
             exec,
+            span,
         } = self.clone();
+        // spawned tasks run in the current span.
+        let _entered = span.enter();
         exec.spawn_blocking(move || {
+            let request_id = request_id.as_ref().map(RequestId::as_str);
             info!(
+                request_id,
                 "<-- {} {} {}ms {} {}",
                 method,
                 uri,
@@ -84,6 +104,19 @@ impl LogTask {
     }
 }
 
+impl<S> StreamLogger<S> {
+    /// Construct a logger stream, log at once if the body is known to be empty.
+    fn new(stream: S, len: Option<u64>, task: LogTask) -> Self {
+        match len {
+            Some(0) => StreamLogger::Logging {
+                handler: task.log(),
+                last: None,
+            },
+            _ => StreamLogger::Polling { stream, len, task },
+        }
+    }
+}
+
 impl<S> Stream for StreamLogger<S>
 where
     S: 'static + Send + Send + Unpin + Stream<Item = io::Result<Bytes>>,
@@ -92,25 +125,40 @@ where
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
         match &mut *self {
-            StreamLogger::Polling { stream, task } => {
+            StreamLogger::Polling { stream, len, task } => {
                 match futures::ready!(Pin::new(stream).poll_next(cx)) {
                     Some(Ok(bytes)) => {
                         task.counter += bytes.len() as u64;
+                        if Some(task.counter) == *len {
+                            let handler = task.log();
+                            *self = StreamLogger::Logging {
+                                handler,
+                                last: Some(bytes),
+                            };
+                            return self.poll_next(cx);
+                        }
                         Poll::Ready(Some(Ok(bytes)))
                     }
                     None => {
                         let handler = task.log();
-                        *self = StreamLogger::Logging(handler);
+                        *self = StreamLogger::Logging {
+                            handler,
+                            last: None,
+                        };
                         self.poll_next(cx)
                     }
                     err => Poll::Ready(err),
                 }
             }
 
-            StreamLogger::Logging(handler) => {
-                futures::ready!(Pin::new(handler).poll(cx));
+            StreamLogger::Logging { handler, last } => {
+                let _ = futures::ready!(Pin::new(handler).poll(cx));
+                let last = last.take();
                 *self = StreamLogger::Complete;
-                self.poll_next(cx)
+                match last {
+                    Some(bytes) => Poll::Ready(Some(Ok(bytes))),
+                    None => self.poll_next(cx),
+                }
             }
 
             StreamLogger::Complete => Poll::Ready(None),
@@ -122,8 +170,17 @@ where
 ///
 /// Based on crate `log`, the log level must be greater than `INFO` to log all information,
 /// and should be greater than `ERROR` when you need error information only.
+///
+/// The request id is recorded as a field if it's set by a previous middleware,
+/// like `roa::request_id::request_id`.
 pub async fn logger<S>(ctx: &mut Context<S>, next: Next<'_>) -> Result {
-    info!("--> {} {}", ctx.method(), ctx.uri().path());
+    let request_id = ctx.request_id().cloned();
+    info!(
+        request_id = request_id.as_ref().map(RequestId::as_str),
+        "--> {} {}",
+        ctx.method(),
+        ctx.uri().path()
+    );
     let start = Instant::now();
     let mut result = next.await;
 
@@ -143,28 +200,64 @@ pub async fn logger<S>(ctx: &mut Context<S>, next: Next<'_>) -> Result {
                 // take unexposed message
                 mem::take(&mut status.message)
             };
-            ctx.exec
+            let _ = ctx
+                .exec
                 .spawn_blocking(move || {
-                    error!("<-- {} {} {}\n{}", method, uri, status_code, message,);
+                    let request_id = request_id.as_ref().map(RequestId::as_str);
+                    error!(
+                        request_id,
+                        "<-- {} {} {}\n{}", method, uri, status_code, message,
+                    );
                 })
-                .await
+                .await;
         }
         Ok(_) => {
             let status_code = ctx.status();
             // logging when body polling complete.
-            let logger = StreamLogger::Polling {
-                stream: mem::take(&mut ctx.resp.body),
-                task: LogTask {
+            let len = ctx.resp.body.exact_len();
+            ctx.resp.body.map_stream(|stream| {
+                let task = LogTask {
                     counter: 0,
                     method,
                     uri,
+                    request_id,
                     status_code,
                     start,
                     exec,
-                },
-            };
-            ctx.resp.write_stream(logger);
+                    span: Span::current(),
+                };
+                StreamLogger::new(stream, len, task)
+            });
         }
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::HttpBody;
+
+    use super::logger;
+    use crate::http::HeaderMap;
+    use crate::test::TestClient;
+    use crate::{App, Context};
+
+    #[tokio::test]
+    async fn keep_len_and_trailers() -> Result<(), Box<dyn std::error::Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", "0".parse()?);
+            ctx.resp.write("Hello, World").set_trailers(trailers);
+            Ok(())
+        }
+        let client = TestClient::new(App::new().gate(logger).end(end));
+        let mut resp = client.get("/").send().await;
+        assert_eq!(Some(12), resp.body.exact_len());
+        while let Some(data) = resp.body.data().await {
+            data?;
+        }
+        let trailers = resp.body.trailers().await?.expect("trailers are lost");
+        assert_eq!("0", trailers["grpc-status"]);
+        Ok(())
+    }
+}
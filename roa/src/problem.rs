@@ -0,0 +1,204 @@
+//! This module provides an error handler `problem_json`,
+//! which renders status as [RFC 7807](https://tools.ietf.org/html/rfc7807) problem details.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::{App, Context, throw};
+//! use roa::http::StatusCode;
+//! use roa::http::header::CONTENT_TYPE;
+//! use roa::problem::problem_json;
+//! use roa::test::TestClient;
+//! use serde_json::Value;
+//!
+//! async fn end(_ctx: &mut Context) -> roa::Result {
+//!     throw!(StatusCode::BAD_REQUEST, "invalid user id")
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let app = App::new().on_error(problem_json).end(end);
+//!     let resp = TestClient::new(app).get("/").send().await;
+//!     resp.assert_status(StatusCode::BAD_REQUEST)
+//!         .assert_header(CONTENT_TYPE, "application/problem+json");
+//!     let problem: Value = resp.json().await?;
+//!     assert_eq!("Bad Request", problem["title"]);
+//!     assert_eq!(400, problem["status"]);
+//!     assert_eq!("invalid user id", problem["detail"]);
+//!     Ok(())
+//! }
+//! ```
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::http::header::CONTENT_TYPE;
+use crate::http::HeaderValue;
+use crate::{Context, RequestId, Status};
+
+/// The content type of problem details.
+pub const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// A problem details object.
+#[derive(Debug, Serialize)]
+pub struct Problem<'a> {
+    /// A URI reference that identifies the problem type.
+    #[serde(rename = "type")]
+    pub kind: &'a str,
+
+    /// A short, human-readable summary of the problem type.
+    pub title: &'a str,
+
+    /// The HTTP status code.
+    pub status: u16,
+
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<&'a str>,
+
+    /// A machine-readable error code, as an extension member.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'a str>,
+
+    /// Structured details, as an extension member.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<&'a Value>,
+
+    /// The id of the request, as an extension member.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<&'a str>,
+}
+
+impl<'a> Problem<'a> {
+    /// Construct a problem from status.
+    /// The message is used as detail only if it's exposed.
+    pub fn new(status: &'a Status) -> Self {
+        let detail = if status.expose && !status.message.is_empty() {
+            Some(status.message.as_str())
+        } else {
+            None
+        };
+        Self {
+            kind: "about:blank",
+            title: status.status_code.canonical_reason().unwrap_or("Unknown"),
+            status: status.status_code.as_u16(),
+            detail,
+            code: status.code.as_deref(),
+            details: status.details.as_ref(),
+            request_id: None,
+        }
+    }
+}
+
+/// An error handler rendering status as problem+json.
+///
+/// Code and details of status are always rendered, so is the request id if it's set,
+/// while messages not exposed are logged by `tracing::error!` instead.
+pub async fn problem_json<S>(ctx: &mut Context<S>, status: Status) {
+    ctx.resp.status = status.status_code;
+    let request_id = ctx.request_id().cloned();
+    let problem = Problem {
+        request_id: request_id.as_ref().map(RequestId::as_str),
+        ..Problem::new(&status)
+    };
+    let body = serde_json::to_vec(&problem).expect("fail to serialize problem");
+    ctx.resp.headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+    );
+    ctx.resp.write(body);
+    if !status.expose {
+        let _ = ctx
+            .exec
+            .spawn_blocking(move || log(&status, request_id.as_ref()))
+            .await;
+    }
+}
+
+/// Log an uncaught status with its source chain.
+fn log(status: &Status, request_id: Option<&RequestId>) {
+    let request_id = request_id.map(RequestId::as_str);
+    let causes: Vec<String> = status
+        .chain()
+        .map(ToString::to_string)
+        // the message is usually converted from the source
+        .skip_while(|cause| *cause == status.message)
+        .collect();
+    if causes.is_empty() {
+        tracing::error!(request_id, "Uncaught status: {}", status)
+    } else {
+        tracing::error!(
+            request_id,
+            "Uncaught status: {}, caused by: {}",
+            status,
+            causes.join(": ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::problem_json;
+    use crate::http::header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+    use crate::http::StatusCode;
+    use crate::request_id::X_REQUEST_ID;
+    use crate::test::TestClient;
+    use crate::{status, App};
+
+    #[tokio::test]
+    async fn hide_unexposed_message() -> Result<(), Box<dyn std::error::Error>> {
+        let app = App::new().on_error(problem_json).end(status!(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "secret",
+            false
+        ));
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .assert_header(CONTENT_TYPE, "application/problem+json");
+        let problem: Value = resp.json().await?;
+        assert_eq!("about:blank", problem["type"]);
+        assert_eq!("Internal Server Error", problem["title"]);
+        assert_eq!(500, problem["status"]);
+        assert!(problem.get("detail").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn code_and_details() -> Result<(), Box<dyn std::error::Error>> {
+        let app = App::new().on_error(problem_json).end(status!(
+            StatusCode::TOO_MANY_REQUESTS, "slow down";
+            with_code("rate_limited"),
+            with_details(json!({ "limit": 100 })),
+            with_header(RETRY_AFTER, HeaderValue::from_static("60")),
+        ));
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::TOO_MANY_REQUESTS)
+            .assert_header(RETRY_AFTER, "60");
+        let problem: Value = resp.json().await?;
+        assert_eq!("slow down", problem["detail"]);
+        assert_eq!("rate_limited", problem["code"]);
+        assert_eq!(100, problem["details"]["limit"]);
+        assert!(problem.get("request_id").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn request_id() -> Result<(), Box<dyn std::error::Error>> {
+        let app = App::new()
+            .on_error(problem_json)
+            .gate(crate::request_id::request_id)
+            .end(status!(StatusCode::BAD_REQUEST));
+        let resp = TestClient::new(app)
+            .get("/")
+            .header(X_REQUEST_ID, "abc")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST)
+            .assert_header(X_REQUEST_ID, "abc");
+        let problem: Value = resp.json().await?;
+        assert_eq!("abc", problem["request_id"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+//! This module provides a middleware `request_id`,
+//! reading the request id from `X-Request-Id` or generating a UUID v4 if it's missing.
+//!
+//! The id is stored in context as `RequestId`, echoed in response headers,
+//! and recorded as a field by `roa::logger` and the default error handler.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::request_id::{request_id, X_REQUEST_ID};
+//! use roa::logger::logger;
+//! use roa::{App, Context};
+//! use roa::http::StatusCode;
+//! use roa::test::TestClient;
+//!
+//! async fn end(ctx: &mut Context) -> roa::Result {
+//!     let id = ctx.request_id().unwrap().to_string();
+//!     ctx.resp.write(id);
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     // request id should be set before logger
+//!     let app = App::new().gate(request_id).gate(logger).end(end);
+//!     let client = TestClient::new(app);
+//!     let resp = client.get("/").header(X_REQUEST_ID, "abc").send().await;
+//!     resp.assert_status(StatusCode::OK)
+//!         .assert_header(X_REQUEST_ID, "abc");
+//!     assert_eq!("abc", resp.text().await?);
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use once_cell::sync::Lazy;
+
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::{Context, Middleware, Next, RequestId, Result};
+
+/// The default header of request id.
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+/// The max length of a request id from client.
+const MAX_LEN: usize = 128;
+
+/// A generator of request id.
+type Generator = Box<dyn 'static + Send + Sync + Fn() -> String>;
+
+/// A middleware to set request id, constructed by `SetRequestId::new`.
+///
+/// Request ids from client are trusted only if they are visible ASCII strings
+/// no longer than 128 bytes, otherwise new ones are generated.
+///
+/// ### Example
+/// ```rust
+/// use roa::request_id::SetRequestId;
+/// use roa::http::header::HeaderName;
+/// use roa::App;
+///
+/// let set_request_id = SetRequestId::new()
+///     .header(HeaderName::from_static("x-trace-id"))
+///     .generator(|| "fixed".to_string());
+/// let app = App::new().gate(set_request_id).end(());
+/// ```
+pub struct SetRequestId {
+    header: HeaderName,
+    generator: Generator,
+}
+
+impl SetRequestId {
+    /// Construct a middleware reading `X-Request-Id` and generating UUID v4.
+    pub fn new() -> Self {
+        Self {
+            header: HeaderName::from_static(X_REQUEST_ID),
+            generator: Box::new(uuid_v4),
+        }
+    }
+
+    /// Set the header to read and echo request id.
+    pub fn header(mut self, header: HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Set the generator of request id.
+    pub fn generator(mut self, generator: impl 'static + Send + Sync + Fn() -> String) -> Self {
+        self.generator = Box::new(generator);
+        self
+    }
+
+    /// Get the trusted request id from client, or generate a new one.
+    fn request_id<S>(&self, ctx: &Context<S>) -> String {
+        match ctx.get(&self.header) {
+            Some(id)
+                if !id.is_empty()
+                    && id.len() <= MAX_LEN
+                    && id.bytes().all(|byte| byte.is_ascii_graphic()) =>
+            {
+                id.to_string()
+            }
+            _ => (self.generator)(),
+        }
+    }
+}
+
+impl Default for SetRequestId {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, S> Middleware<'a, S> for SetRequestId {
+    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
+        let id = self.request_id(ctx);
+        match HeaderValue::from_str(&id) {
+            Ok(value) => {
+                ctx.resp.headers.insert(self.header.clone(), value);
+            }
+            Err(err) => tracing::warn!("invalid request id {:?}: {}", id, err),
+        }
+        ctx.insert_ext(RequestId::new(id));
+        next.await
+    }
+}
+
+/// A middleware to set request id by the default `SetRequestId`.
+pub async fn request_id<S>(ctx: &mut Context<S>, next: Next<'_>) -> Result {
+    static DEFAULT: Lazy<SetRequestId> = Lazy::new(SetRequestId::new);
+    DEFAULT.handle(ctx, next).await
+}
+
+/// Generate a random UUID v4.
+fn uuid_v4() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let mut id = String::with_capacity(36);
+    for (index, byte) in bytes.iter().enumerate() {
+        if let 4 | 6 | 8 | 10 = index {
+            id.push('-');
+        }
+        write!(id, "{:02x}", byte).expect("fail to write string");
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{request_id, uuid_v4, SetRequestId, X_REQUEST_ID};
+    use crate::http::header::HeaderName;
+    use crate::http::StatusCode;
+    use crate::test::TestClient;
+    use crate::{throw, App, Context};
+
+    async fn end(ctx: &mut Context) -> crate::Result {
+        let id = ctx.request_id().unwrap().to_string();
+        if ctx.uri().path() == "/error" {
+            throw!(StatusCode::BAD_REQUEST, id);
+        }
+        ctx.resp.write(id);
+        Ok(())
+    }
+
+    #[test]
+    fn uuid() {
+        let id = uuid_v4();
+        assert_eq!(36, id.len());
+        assert_eq!(Some('4'), id.chars().nth(14));
+        assert!(matches!(id.chars().nth(19), Some('8' | '9' | 'a' | 'b')));
+        assert_ne!(id, uuid_v4());
+    }
+
+    #[tokio::test]
+    async fn propagate() -> Result<(), Box<dyn std::error::Error>> {
+        let client = TestClient::new(App::new().gate(request_id).end(end));
+        let resp = client.get("/").header(X_REQUEST_ID, "abc").send().await;
+        resp.assert_status(StatusCode::OK)
+            .assert_header(X_REQUEST_ID, "abc");
+        assert_eq!("abc", resp.text().await?);
+
+        // echo on error
+        let resp = client
+            .get("/error")
+            .header(X_REQUEST_ID, "abc")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST)
+            .assert_header(X_REQUEST_ID, "abc");
+        assert_eq!("abc", resp.text().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn generate() -> Result<(), Box<dyn std::error::Error>> {
+        let client = TestClient::new(App::new().gate(request_id).end(end));
+        let resp = client.get("/").send().await;
+        resp.assert_status(StatusCode::OK);
+        let id = resp.text().await?;
+        assert_eq!(36, id.len());
+
+        // untrusted id
+        let resp = client.get("/").header(X_REQUEST_ID, "a b").send().await;
+        assert_ne!("a b", resp.text().await?);
+
+        let set_request_id = SetRequestId::new()
+            .header(HeaderName::from_static("x-trace-id"))
+            .generator(|| "fixed".to_string());
+        let client = TestClient::new(App::new().gate(set_request_id).end(end));
+        let resp = client.get("/").header(X_REQUEST_ID, "abc").send().await;
+        resp.assert_status(StatusCode::OK)
+            .assert_header("x-trace-id", "fixed");
+        assert_eq!("fixed", resp.text().await?);
+        Ok(())
+    }
+}
@@ -47,31 +47,48 @@
 //! ```
 //!
 
+mod de;
 mod endpoints;
 mod err;
+mod meta;
+#[cfg(feature = "openapi")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "openapi")))]
+pub mod openapi;
 mod path;
+mod tree;
+mod url;
 
+use std::collections::HashMap;
 use std::convert::AsRef;
 use std::result::Result as StdResult;
+use std::sync::Arc;
 
 #[doc(inline)]
 pub use endpoints::*;
 use err::Conflict;
 #[doc(inline)]
 pub use err::RouterError;
-use path::{join_path, standardize_path, Path, RegexPath};
+pub use meta::RouteInfo;
+use path::{join_path, segments, Pattern};
 use percent_encoding::percent_decode_str;
-use radix_trie::Trie;
+use serde::de::DeserializeOwned;
+use tree::Node;
+pub use url::Urls;
 
-use crate::http::StatusCode;
+use crate::http::{Method, StatusCode};
+#[doc(no_inline)]
+pub use crate::Meta;
 use crate::{
-    async_trait, throw, Boxed, Context, Endpoint, EndpointExt, Middleware, MiddlewareExt, Result,
-    Shared, Status, Variable,
+    throw, Chain, Context, Endpoint, Erase, Erasure, Local, Middleware, MiddlewareExt, Result,
+    Status, Threaded, Variable,
 };
 
 /// A private scope to store and load variables in Context::storage.
 struct RouterScope;
 
+/// A private scope to store all router parameters in Context::storage.
+struct RouterParamsScope;
+
 /// A context extension.
 /// This extension must be used in `Router`,
 /// otherwise you cannot get expected router parameters.
@@ -137,18 +154,152 @@ pub trait RouterParam {
     ///
     /// ```
     fn param<'a>(&self, name: &'a str) -> Option<Variable<'a, String>>;
+
+    /// Deserialize all router parameters into a struct, a tuple, or a single value,
+    /// throw 400 BAD REQUEST naming the offending parameter if it's invalid.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use roa::router::{Router, RouterParam};
+    /// use roa::{App, Context, Status};
+    /// use roa::http::StatusCode;
+    /// use roa::test::TestClient;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Post {
+    ///     user: String,
+    ///     id: u64,
+    /// }
+    ///
+    /// async fn test(ctx: &mut Context) -> Result<(), Status> {
+    ///     let post: Post = ctx.params()?;
+    ///     let (user, id): (String, u64) = ctx.params()?;
+    ///     assert_eq!(post.user, user);
+    ///     assert_eq!(post.id, id);
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let router = Router::new().on("/:user/posts/:id", test);
+    ///     let client = TestClient::new(App::new().end(router.routes("/")?));
+    ///     let resp = client.get("/hexilee/posts/1").send().await;
+    ///     resp.assert_status(StatusCode::OK);
+    ///
+    ///     let resp = client.get("/hexilee/posts/x").send().await;
+    ///     resp.assert_status(StatusCode::BAD_REQUEST);
+    ///     assert!(resp.text().await?.starts_with("invalid path parameter `id`"));
+    ///     Ok(())
+    /// }
+    /// ```
+    fn params<T: DeserializeOwned>(&self) -> Result<T>;
+
+    /// Build the path of a named route by `Urls` of the current `RouteTable`,
+    /// throw 500 INTERNAL SERVER ERROR if the route is unknown or params are invalid.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use roa::router::{get, Router, RouterParam};
+    /// use roa::{App, Context, Status};
+    /// use roa::test::TestClient;
+    ///
+    /// async fn create(ctx: &mut Context) -> Result<(), Status> {
+    ///     let location = ctx.url_for("user", [("id", "1")])?;
+    ///     ctx.resp.write(location);
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn query(ctx: &mut Context) -> Result<(), Status> {
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let router = Router::new()
+    ///         .on("/", get(create))
+    ///         .named("user", "/:id", get(query));
+    ///     let client = TestClient::new(App::new().end(router.routes("/user")?));
+    ///     let resp = client.get("/user").send().await;
+    ///     assert_eq!("/user/1", resp.text().await?);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn url_for<K, V>(&self, name: &str, params: impl IntoIterator<Item = (K, V)>) -> Result<String>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>;
 }
 
+/// The route pattern matched by `RouteTable`, stored as a context extension.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa::router::{MatchedRoute, Router};
+/// use roa::{App, Context, Status};
+///
+/// async fn test(ctx: &mut Context) -> Result<(), Status> {
+///     assert_eq!("/user/:id", ctx.ext::<MatchedRoute>().unwrap().as_str());
+///     Ok(())
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let router = Router::new().on("/:id", test);
+/// let app = App::new().end(router.routes("/user")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRoute(Arc<str>);
+
+impl MatchedRoute {
+    /// Get the route pattern as str.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Methods and their metadata of an endpoint, `None` if they are unknown.
+type Methods = Option<Vec<(Method, Meta)>>;
+
+/// An endpoint or middleware chained to the middleware of a router erased by `K`.
+type Gated<S, K, T> = Chain<<K as Erasure<S>>::Middleware, T>;
+
 /// A builder of `RouteTable`.
-pub struct Router<S> {
-    middleware: Shared<S>,
-    endpoints: Vec<(String, Boxed<S>)>,
+///
+/// Endpoints and middlewares are erased by the kind `K`, so they must be `Send + Sync`
+/// by default. Routers constructed by `Router::local` accept `!Send` ones holding `Rc` or `RefCell`,
+/// their route tables can only be served on the current thread, like by `roa::tcp::ThreadPerCore`.
+pub struct Router<S, K: Erasure<S> = Threaded> {
+    /// `None` if there is no gate, so endpoints are erased without an empty middleware.
+    middleware: Option<K::Middleware>,
+    endpoints: Vec<(String, K::Endpoint, Methods)>,
+    names: Vec<(String, String)>,
 }
 
 /// An endpoint to route request by uri path.
-pub struct RouteTable<S> {
-    static_route: Trie<String, Boxed<S>>,
-    dynamic_route: Vec<(RegexPath, Boxed<S>)>,
+///
+/// Routes are matched by a segment-aware radix tree, static segments take priority over
+/// variables like `:id`, and variables take priority over wildcards like `*{path}`,
+/// regardless of the registration order.
+///
+/// Variables can be constrained by an inline regex like `:id(\d+)` or `*{path(.+\.js)}`,
+/// or a predefined type like `:id<u64>` or `:slug<uuid>`.
+/// Values failing the constraint don't match, so routes with different constraints can coexist.
+/// Predefined types are integers, floats, `bool` and `uuid`.
+///
+/// Constrained variables in the same position are tried in registration order,
+/// before the unconstrained one. Overlaps of different constraints are not detected,
+/// so a value satisfying both `:id(\d+)` and `:n<u64>` matches the one registered first.
+/// Variables with the same constraint but different names are rejected as ambiguous.
+pub struct RouteTable<S, K: Erasure<S> = Threaded> {
+    root: Node<K::Endpoint>,
+    urls: Urls,
+    routes: Vec<RouteInfo>,
 }
 
 impl<S> Router<S>
@@ -157,81 +308,197 @@ where
 {
     /// Construct a new router.
     pub fn new() -> Self {
+        Self::empty()
+    }
+}
+
+impl<S> Router<S, Local>
+where
+    S: 'static,
+{
+    /// Construct a new router accepting `!Send` endpoints and middlewares.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use roa::router::Router;
+    /// use roa::{App, Context, Result};
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let counter = Rc::new(Cell::new(0));
+    /// let router = Router::local().on("/", move |ctx: &mut Context| {
+    ///     counter.set(counter.get() + 1);
+    ///     async { Ok(()) }
+    /// });
+    /// let app = App::new().end(router.routes("/")?);
+    /// # Ok::<_, roa::router::RouterError>(())
+    /// ```
+    pub fn local() -> Self {
+        Self::empty()
+    }
+}
+
+impl<S, K> Router<S, K>
+where
+    S: 'static,
+    K: Erasure<S>,
+{
+    fn empty() -> Self {
         Self {
-            middleware: ().shared(),
+            middleware: None,
             endpoints: Vec::new(),
+            names: Vec::new(),
         }
     }
 
     /// Register a new endpoint.
-    pub fn on(mut self, path: &'static str, endpoint: impl for<'a> Endpoint<'a, S>) -> Self {
+    ///
+    /// Methods and metadata of the endpoint are listed by `RouteTable::iter`
+    /// if they are known by `Endpoint::methods`, like those of `get(query).post(create)`.
+    pub fn on<E>(mut self, path: &'static str, endpoint: E) -> Self
+    where
+        E: for<'a> Endpoint<'a, S>,
+        K: Erase<S, E> + Erase<S, Gated<S, K, E>>,
+    {
+        let methods = endpoint.methods();
         self.endpoints
-            .push((path.to_string(), self.register(endpoint)));
+            .push((path.to_string(), self.register(endpoint), methods));
         self
     }
 
+    /// Register a new endpoint with a name, to build its URL by `Urls`.
+    pub fn named<E>(mut self, name: &'static str, path: &'static str, endpoint: E) -> Self
+    where
+        E: for<'a> Endpoint<'a, S>,
+        K: Erase<S, E> + Erase<S, Gated<S, K, E>>,
+    {
+        self.names.push((name.to_string(), path.to_string()));
+        self.on(path, endpoint)
+    }
+
     /// Chain an endpoint to Router::middleware.
-    fn register(&self, endpoint: impl for<'a> Endpoint<'a, S>) -> Boxed<S> {
-        self.middleware.clone().end(endpoint).boxed()
+    fn register<E>(&self, endpoint: E) -> K::Endpoint
+    where
+        E: for<'a> Endpoint<'a, S>,
+        K: Erase<S, E> + Erase<S, Gated<S, K, E>>,
+    {
+        match self.middleware.clone() {
+            Some(middleware) => <K as Erase<S, _>>::boxed(middleware.end(endpoint)),
+            None => <K as Erase<S, E>>::boxed(endpoint),
+        }
     }
 
     /// Include another router with prefix.
-    pub fn include(mut self, prefix: &'static str, router: Router<S>) -> Self {
-        for (path, endpoint) in router.endpoints {
+    pub fn include(mut self, prefix: &'static str, router: Router<S, K>) -> Self
+    where
+        K: Erase<S, Gated<S, K, <K as Erasure<S>>::Endpoint>>,
+    {
+        for (path, endpoint, methods) in router.endpoints {
+            let endpoint = match self.middleware.clone() {
+                Some(middleware) => K::boxed(middleware.end(endpoint)),
+                None => endpoint,
+            };
             self.endpoints
-                .push((join_path([prefix, path.as_str()]), self.register(endpoint)))
+                .push((join_path([prefix, path.as_str()]), endpoint, methods))
+        }
+        for (name, path) in router.names {
+            self.names.push((name, join_path([prefix, path.as_str()])))
         }
         self
     }
 
     /// Chain a middleware to Router::middleware.
-    pub fn gate(self, next: impl for<'a> Middleware<'a, S>) -> Router<S> {
+    pub fn gate<M>(self, next: M) -> Self
+    where
+        M: for<'a> Middleware<'a, S>,
+        K: Erase<S, M> + Erase<S, Gated<S, K, M>>,
+    {
         let Self {
             middleware,
             endpoints,
+            names,
         } = self;
+        let middleware = match middleware {
+            Some(middleware) => <K as Erase<S, _>>::shared(middleware.chain(next)),
+            None => <K as Erase<S, M>>::shared(next),
+        };
         Self {
-            middleware: middleware.chain(next).shared(),
+            middleware: Some(middleware),
             endpoints,
+            names,
         }
     }
 
     /// Build RouteTable with path prefix.
-    pub fn routes(self, prefix: &'static str) -> StdResult<RouteTable<S>, RouterError> {
-        let mut route_table = RouteTable::default();
-        for (raw_path, endpoint) in self.endpoints {
-            route_table.insert(join_path([prefix, raw_path.as_str()]), endpoint)?;
+    pub fn routes(self, prefix: &'static str) -> StdResult<RouteTable<S, K>, RouterError> {
+        let mut route_table = RouteTable::new();
+        let mut names = HashMap::new();
+        for (name, raw_path) in self.names {
+            let pattern: Pattern = join_path([prefix, raw_path.as_str()]).parse()?;
+            names.insert(pattern.raw.clone(), name.clone());
+            route_table.urls.insert(name, pattern)?;
+        }
+        for (raw_path, endpoint, methods) in self.endpoints {
+            let pattern: Pattern = join_path([prefix, raw_path.as_str()]).parse()?;
+            route_table.routes.push(RouteInfo {
+                name: names.get(&pattern.raw).cloned(),
+                pattern: pattern.raw.clone(),
+                methods,
+            });
+            route_table.root.insert(pattern, endpoint)?;
         }
         Ok(route_table)
     }
 }
 
-impl<S> RouteTable<S>
+impl<S, K> RouteTable<S, K>
 where
     S: 'static,
+    K: Erasure<S>,
 {
     fn new() -> Self {
         Self {
-            static_route: Trie::new(),
-            dynamic_route: Vec::new(),
+            root: Node::default(),
+            urls: Urls::default(),
+            routes: Vec::new(),
         }
     }
 
-    /// Insert endpoint to table.
-    fn insert(
-        &mut self,
-        raw_path: impl AsRef<str>,
-        endpoint: Boxed<S>,
-    ) -> StdResult<(), RouterError> {
-        match raw_path.as_ref().parse()? {
-            Path::Static(path) => {
-                if self.static_route.insert(path.clone(), endpoint).is_some() {
-                    return Err(Conflict::Path(path).into());
-                }
-            }
-            Path::Dynamic(regex_path) => self.dynamic_route.push((regex_path, endpoint)),
-        }
-        Ok(())
+    /// Iterate registered routes in order of registration.
+    ///
+    /// ### Example
+    ///
+    /// ```rust
+    /// use roa::router::{get, Router};
+    /// use roa::http::Method;
+    /// use roa::{Context, Status};
+    ///
+    /// async fn end(ctx: &mut Context) -> Result<(), Status> {
+    ///     Ok(())
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let router = Router::new()
+    ///     .named("user", "/user/:id", get(end).delete(end))
+    ///     .on("/graphql", end);
+    /// let route_table = router.routes("/api")?;
+    /// let routes: Vec<_> = route_table.iter().collect();
+    /// assert_eq!("/api/user/:id", routes[0].pattern);
+    /// assert_eq!(Some("user"), routes[0].name.as_deref());
+    /// let methods: Vec<_> = routes[0].methods.iter().flatten().map(|(method, _)| method).collect();
+    /// assert_eq!(vec![Method::GET, Method::DELETE], methods);
+    /// assert!(routes[1].methods.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &RouteInfo> {
+        self.routes.iter()
+    }
+
+    /// Get the URL builder of named routes.
+    #[inline]
+    pub fn urls(&self) -> &Urls {
+        &self.urls
     }
 }
 
@@ -253,38 +520,41 @@ where
     }
 }
 
-#[async_trait(?Send)]
-impl<'a, S> Endpoint<'a, S> for RouteTable<S>
+impl<'a, S, K> Endpoint<'a, S> for RouteTable<S, K>
 where
     S: 'static,
+    K: Erasure<S>,
 {
     #[inline]
     async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
         let uri = ctx.uri();
-        // standardize path
-        let path = standardize_path(&percent_decode_str(uri.path()).decode_utf8().map_err(
-            |err| {
+        // split before decoding, so an encoded slash stays in its segment.
+        let segments = segments(uri.path())
+            .into_iter()
+            .map(|segment| percent_decode_str(segment).decode_utf8())
+            .collect::<StdResult<Vec<_>, _>>()
+            .map_err(|err| {
                 Status::new(
                     StatusCode::BAD_REQUEST,
                     format!("{}\npath `{}` is not a valid utf-8 string", err, uri.path()),
                     true,
                 )
-            },
-        )?);
-
-        // search static routes
-        if let Some(end) = self.static_route.get(&path) {
-            return end.call(ctx).await;
-        }
-
-        // search dynamic routes
-        for (regexp_path, end) in self.dynamic_route.iter() {
-            if let Some(cap) = regexp_path.re.captures(&path) {
-                for var in regexp_path.vars.iter() {
-                    ctx.store_scoped(RouterScope, var.to_string(), cap[var.as_str()].to_string());
+            })?;
+        let segments = segments.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+
+        let mut values = Vec::new();
+        if let Some(route) = self.root.find(&segments, &mut values) {
+            if !route.vars.is_empty() {
+                let mut params = Vec::with_capacity(route.vars.len());
+                for (var, value) in route.vars.iter().zip(values) {
+                    params.push((var.clone(), value.clone()));
+                    ctx.store_scoped(RouterScope, var.clone(), value);
                 }
-                return end.call(ctx).await;
+                ctx.store_scoped(RouterParamsScope, "", params);
             }
+            ctx.insert_ext(MatchedRoute(route.pattern.clone()));
+            ctx.insert_ext(self.urls.clone());
+            return route.value.call(ctx).await;
         }
 
         // 404 NOT FOUND
@@ -307,6 +577,30 @@ impl<S> RouterParam for Context<S> {
     fn param<'a>(&self, name: &'a str) -> Option<Variable<'a, String>> {
         self.load_scoped::<RouterScope, String>(name)
     }
+    #[inline]
+    fn params<T: DeserializeOwned>(&self) -> Result<T> {
+        let params = self
+            .load_scoped::<RouterParamsScope, Vec<(String, String)>>("")
+            .map(Variable::value)
+            .unwrap_or_default();
+        de::from_params(&params).map_err(|err| Status::new(StatusCode::BAD_REQUEST, err, true))
+    }
+    #[inline]
+    fn url_for<K, V>(&self, name: &str, params: impl IntoIterator<Item = (K, V)>) -> Result<String>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let urls = self.ext::<Urls>().ok_or_else(|| {
+            Status::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "url_for must be used in `RouteTable`",
+                false,
+            )
+        })?;
+        urls.url_for(name, params)
+            .map_err(|err| Status::new(StatusCode::INTERNAL_SERVER_ERROR, err, false))
+    }
 }
 
 #[cfg(all(test, feature = "tcp"))]
@@ -315,10 +609,11 @@ mod tests {
     use percent_encoding::NON_ALPHANUMERIC;
     use tokio::task::spawn;
 
-    use super::Router;
-    use crate::http::StatusCode;
+    use super::{allow, deny, get, MatchedRoute, Meta, RouteInfo, Router, RouterParam};
+    use crate::http::{Method, StatusCode};
     use crate::tcp::Listener;
-    use crate::{App, Context, Next, Status};
+    use crate::test::TestClient;
+    use crate::{App, Context, EndpointExt, MiddlewareExt, Next, Status};
 
     async fn gate(ctx: &mut Context, next: Next<'_>) -> Result<(), Status> {
         ctx.store("id", "0".to_string());
@@ -365,6 +660,197 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ambiguous_path() -> Result<(), Box<dyn std::error::Error>> {
+        let router = Router::new().on("/user/:id", test).on("/user/:name", test);
+        let err = router.routes("/").err().unwrap();
+        assert_eq!(
+            "Conflict! ambiguous paths: `/user/:id` and `/user/:name`",
+            err.to_string()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn constraint() -> Result<(), Box<dyn std::error::Error>> {
+        async fn echo(ctx: &mut Context) -> Result<(), Status> {
+            let route = ctx.ext::<MatchedRoute>().unwrap().as_str().to_string();
+            ctx.resp.write(route);
+            Ok(())
+        }
+        let router = Router::new()
+            .on(r"/users/:id(\d+)", echo)
+            .on("/posts/:id<u64>", echo)
+            .on("/posts/:slug<uuid>", echo)
+            .on(r"/files/*{path(.+\.js)}", echo);
+        let client = TestClient::new(App::new().end(router.routes("/")?));
+        client
+            .get("/users/1")
+            .send()
+            .await
+            .assert_status(StatusCode::OK);
+        client
+            .get("/users/abc")
+            .send()
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        let resp = client.get("/posts/1").send().await;
+        assert_eq!("/posts/:id<u64>", resp.text().await?);
+        let resp = client
+            .get("/posts/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .send()
+            .await;
+        assert_eq!("/posts/:slug<uuid>", resp.text().await?);
+        client
+            .get("/posts/-1")
+            .send()
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        client
+            .get("/files/js/app.js")
+            .send()
+            .await
+            .assert_status(StatusCode::OK);
+        client
+            .get("/files/css/app.css")
+            .send()
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        Ok(())
+    }
+
+    #[test]
+    fn named_routes() -> Result<(), Box<dyn std::error::Error>> {
+        let user_router =
+            Router::new()
+                .named("user", "/:id<u64>", test)
+                .named("avatar", "/:id/*{path}", test);
+        let router = Router::new()
+            .named("root", "/", test)
+            .include("/user", user_router);
+        let route_table = router.routes("/api")?;
+        let urls = route_table.urls();
+        assert_eq!("/api", urls.url_for("root", [("", ""); 0])?);
+        assert_eq!("/api/user/1", urls.url_for("user", [("id", "1")])?);
+        assert_eq!(
+            "/api/user/1/a%20b/c.png",
+            urls.url_for("avatar", [("id", "1"), ("path", "a b/c.png")])?
+        );
+        assert!(urls.url_for("user", [("id", "x")]).is_err());
+
+        let router = Router::new()
+            .named("user", "/user", test)
+            .named("user", "/users", test);
+        let err = router.routes("/").err().unwrap();
+        assert_eq!("Conflict! conflict route name: `user`", err.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn iter_routes() -> Result<(), Box<dyn std::error::Error>> {
+        let meta = Meta::new().summary("Get a user");
+        let user_router =
+            Router::new()
+                .gate(gate)
+                .named("user", "/:id", get(test).meta(Method::GET, meta));
+        let router = Router::new().on("/", test).include("/user", user_router);
+        let route_table = router.routes("/api")?;
+        let routes: Vec<&RouteInfo> = route_table.iter().collect();
+        assert_eq!(2, routes.len());
+        assert_eq!("/api", routes[0].pattern);
+        assert_eq!(None, routes[0].name);
+        assert!(routes[0].methods.is_none());
+        assert_eq!("/api/user/:id", routes[1].pattern);
+        assert_eq!(Some("user"), routes[1].name.as_deref());
+        let methods = routes[1].methods.as_ref().unwrap();
+        assert_eq!(1, methods.len());
+        assert_eq!(Method::GET, methods[0].0);
+        assert_eq!(Some("Get a user"), methods[0].1.summary.as_deref());
+        Ok(())
+    }
+
+    #[test]
+    fn iter_wrapped_routes() -> Result<(), Box<dyn std::error::Error>> {
+        let meta = Meta::new().summary("Create a user");
+        let users = get(test).post(test).put(test).meta(Method::POST, meta);
+        let router = Router::new()
+            .on("/boxed", get(test).boxed())
+            .on("/gated", gate.end(get(test)))
+            .on("/allow", allow([Method::GET], test))
+            .on("/users", deny([Method::PUT], users));
+        let route_table = router.routes("/")?;
+        // methods with summaries
+        let methods: Vec<_> = route_table
+            .iter()
+            .map(|route| {
+                route.methods.as_ref().map(|methods| {
+                    methods
+                        .iter()
+                        .map(|(method, meta)| (method.clone(), meta.summary.clone()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Some(vec![(Method::GET, None)]),
+                Some(vec![(Method::GET, None)]),
+                Some(vec![(Method::GET, None)]),
+                Some(vec![
+                    (Method::GET, None),
+                    (Method::POST, Some("Create a user".to_string()))
+                ]),
+            ],
+            methods
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn url_for() -> Result<(), Box<dyn std::error::Error>> {
+        async fn redirect(ctx: &mut Context) -> Result<(), Status> {
+            let id = ctx.must_param("id")?;
+            let location = ctx.url_for("user", [("id", id.as_str())])?;
+            ctx.resp.write(location);
+            Ok(())
+        }
+        async fn unknown(ctx: &mut Context) -> Result<(), Status> {
+            ctx.url_for("post", [("id", "1")])?;
+            Ok(())
+        }
+        let router = Router::new()
+            .named("user", "/user/:id", test)
+            .on("/redirect/:id", redirect)
+            .on("/unknown", unknown);
+        let client = TestClient::new(App::new().end(router.routes("/")?));
+        let resp = client.get("/redirect/a%20b").send().await;
+        assert_eq!("/user/a%20b", resp.text().await?);
+        client
+            .get("/unknown")
+            .send()
+            .await
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn route_url_for() -> Result<(), Box<dyn std::error::Error>> {
+        async fn echo(ctx: &mut Context) -> Result<(), Status> {
+            let id = ctx.must_param("id")?;
+            ctx.resp.write(id.to_string());
+            Ok(())
+        }
+        let router = Router::new().named("user", "/user/:id", echo);
+        let route_table = router.routes("/")?;
+        let url = route_table.urls().url_for("user", [("id", "a/b?c d")])?;
+        assert_eq!("/user/a%2Fb%3Fc%20d", url);
+        let client = TestClient::new(App::new().end(route_table));
+        let resp = client.get(&url).send().await;
+        assert_eq!(StatusCode::OK, resp.status);
+        assert_eq!("a/b?c d", resp.text().await?);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn route_not_found() -> Result<(), Box<dyn std::error::Error>> {
         let app = App::new().end(Router::default().routes("/")?);
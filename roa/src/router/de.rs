@@ -0,0 +1,404 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// Error occurring in deserializing router parameters.
+#[derive(Debug)]
+pub struct ParamsError {
+    /// The name of the offending parameter, if any.
+    param: Option<String>,
+    message: String,
+}
+
+impl ParamsError {
+    fn new(param: &str, message: impl ToString) -> Self {
+        Self {
+            param: Some(param.to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    /// Attach the parameter name, if it's not attached yet.
+    fn with_param(mut self, param: &str) -> Self {
+        self.param.get_or_insert_with(|| param.to_string());
+        self
+    }
+}
+
+impl Display for ParamsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.param {
+            Some(param) => write!(f, "invalid path parameter `{}`: {}", param, self.message),
+            None => write!(f, "invalid path parameters: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl de::Error for ParamsError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            param: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Deserialize router parameters into a struct, map, tuple, sequence,
+/// or a single value if there is exactly one parameter.
+pub fn from_params<'de, T>(params: &'de [(String, String)]) -> Result<T, ParamsError>
+where
+    T: de::Deserialize<'de>,
+{
+    T::deserialize(ParamsDeserializer { params })
+}
+
+/// A deserializer of all router parameters.
+struct ParamsDeserializer<'de> {
+    params: &'de [(String, String)],
+}
+
+impl<'de> ParamsDeserializer<'de> {
+    /// Get the only parameter as a value deserializer.
+    fn single(self) -> Result<ValueDeserializer<'de>, ParamsError> {
+        match self.params {
+            [(name, value)] => Ok(ValueDeserializer { name, value }),
+            params => Err(de::Error::custom(format!(
+                "expected exactly one parameter, found {}",
+                params.len()
+            ))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.single()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = ParamsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(ParamsAccess::new(self.params))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(ParamsAccess::new(self.params))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.params.len() != len {
+            return Err(de::Error::invalid_length(self.params.len(), &visitor));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_single! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string deserialize_bytes
+        deserialize_byte_buf deserialize_option deserialize_unit deserialize_identifier
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_unit(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Access parameters as a map or a sequence.
+struct ParamsAccess<'de> {
+    iter: std::slice::Iter<'de, (String, String)>,
+    value: Option<&'de (String, String)>,
+}
+
+impl<'de> ParamsAccess<'de> {
+    fn new(params: &'de [(String, String)]) -> Self {
+        Self {
+            iter: params.iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for ParamsAccess<'de> {
+    type Error = ParamsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            None => Ok(None),
+            Some(param) => {
+                self.value = Some(param);
+                let key: BorrowedStrDeserializer<'de, ParamsError> =
+                    BorrowedStrDeserializer::new(&param.0);
+                seed.deserialize(key).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (name, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        seed.deserialize(ValueDeserializer { name, value })
+            .map_err(|err| err.with_param(name))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+impl<'de> SeqAccess<'de> for ParamsAccess<'de> {
+    type Error = ParamsError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            None => Ok(None),
+            Some((name, value)) => seed
+                .deserialize(ValueDeserializer { name, value })
+                .map(Some)
+                .map_err(|err| err.with_param(name)),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// A deserializer of a single parameter, parsing primitives from str.
+struct ValueDeserializer<'de> {
+    name: &'de str,
+    value: &'de str,
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let value = self.value.parse().map_err(|err| {
+                    ParamsError::new(self.name, format!("cannot parse `{}`: {}", self.value, err))
+                })?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = ParamsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(ParamsError::new(
+                self.name,
+                format!("expected empty value, found `{}`", self.value),
+            ))
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let name = self.name;
+        let value: BorrowedStrDeserializer<'de, ParamsError> =
+            BorrowedStrDeserializer::new(self.value);
+        visitor
+            .visit_enum(value)
+            .map_err(|err| err.with_param(name))
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::from_params;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Post,
+        Comment,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Params {
+        id: u64,
+        kind: Kind,
+        name: Option<String>,
+    }
+
+    #[test]
+    fn deserialize() {
+        let pairs = params(&[("id", "1"), ("kind", "post")]);
+        assert_eq!(
+            Params {
+                id: 1,
+                kind: Kind::Post,
+                name: None
+            },
+            from_params(&pairs).unwrap()
+        );
+        assert_eq!((1u64, Kind::Post), from_params(&pairs).unwrap());
+        assert_eq!(
+            vec!["1".to_string(), "post".to_string()],
+            from_params::<Vec<String>>(&pairs).unwrap()
+        );
+        assert_eq!(
+            Kind::Comment,
+            from_params(&params(&[("kind", "comment")])).unwrap()
+        );
+        assert_eq!(
+            65535,
+            from_params::<u16>(&params(&[("id", "65535")])).unwrap()
+        );
+    }
+
+    #[test]
+    fn name_offending_param() {
+        let pairs = params(&[("id", "x"), ("kind", "post")]);
+        let err = from_params::<Params>(&pairs).unwrap_err().to_string();
+        assert!(
+            err.starts_with("invalid path parameter `id`: cannot parse `x`"),
+            "{}",
+            err
+        );
+
+        let err = from_params::<(u64, u8)>(&params(&[("id", "1"), ("page", "256")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid path parameter `page`"), "{}", err);
+
+        let err = from_params::<Params>(&params(&[("id", "1"), ("kind", "user")]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("invalid path parameter `kind`"), "{}", err);
+
+        let err = from_params::<u64>(&pairs).unwrap_err().to_string();
+        assert_eq!(
+            "invalid path parameters: expected exactly one parameter, found 2",
+            err
+        );
+    }
+}
@@ -4,6 +4,19 @@ mod guard;
 use crate::http::{Method, StatusCode};
 use crate::{throw, Result};
 
+/// Methods allowed in `Guard`, in the order listed by `Dispatcher`.
+static ALL_METHODS: [Method; 9] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::OPTIONS,
+    Method::DELETE,
+    Method::HEAD,
+    Method::TRACE,
+    Method::CONNECT,
+];
+
 #[inline]
 fn method_not_allowed(method: &Method) -> Result {
     throw!(
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::future::Future;
 
 use doc_comment::doc_comment;
 
-use super::method_not_allowed;
+use super::{method_not_allowed, ALL_METHODS};
 use crate::http::Method;
-use crate::{async_trait, Context, Endpoint, Result};
+use crate::router::Meta;
+use crate::{Context, Endpoint, Result};
 
 macro_rules! impl_http_methods {
     ($end:ident, $method:expr) => {
@@ -27,9 +29,8 @@ async fn bar(ctx: &mut Context) -> Result {
 
 let app = App::new().end(get(foo).", stringify!($end), "(bar));
 ```"),
-            pub fn $end(mut self, endpoint: impl for<'a> Endpoint<'a, S>) -> Self {
-                self.0.insert($method, Box::new(endpoint));
-                self
+            pub fn $end<E>(self, endpoint: E) -> Dispatcher<On<E, T>> {
+                self.on($method, endpoint)
             }
         }
     };
@@ -52,15 +53,37 @@ async fn end(ctx: &mut Context) -> Result {
 
 let app = App::new().end(", stringify!($end), "(end));
 ```"),
-            pub fn $end<S>(endpoint: impl for<'a> Endpoint<'a, S>) -> Dispatcher<S> {
-                    Dispatcher::<S>::default().$end(endpoint)
+            pub fn $end<E>(endpoint: E) -> Dispatcher<On<E, ()>> {
+                Dispatcher::default().$end(endpoint)
             }
         }
     };
 }
 
 /// An endpoint wrapper to dispatch requests by http method.
-pub struct Dispatcher<S>(HashMap<Method, Box<dyn for<'a> Endpoint<'a, S>>>);
+///
+/// Endpoints are kept in their own types, so dispatching a request doesn't allocate,
+/// and the dispatcher is `Send + Sync` if all of them are.
+pub struct Dispatcher<T = ()> {
+    endpoints: T,
+    meta: HashMap<Method, Meta>,
+}
+
+/// An endpoint on a method, followed by endpoints registered before it.
+pub struct On<E, T> {
+    method: Method,
+    endpoint: E,
+    rest: T,
+}
+
+/// Endpoints of a `Dispatcher`, the last registered one on a method takes effect.
+pub trait Dispatch<'a, S>: 'static {
+    /// Whether there is an endpoint on the method.
+    fn contains(&self, method: &Method) -> bool;
+
+    /// Call the endpoint on the method of request, or throw `405 Method Not Allowed`.
+    fn dispatch(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result>;
+}
 
 impl_http_functions!(get, Method::GET);
 impl_http_functions!(post, Method::POST);
@@ -72,7 +95,19 @@ impl_http_functions!(head, Method::HEAD);
 impl_http_functions!(trace, Method::TRACE);
 impl_http_functions!(connect, Method::CONNECT);
 
-impl<S> Dispatcher<S> {
+impl<T> Dispatcher<T> {
+    /// Add or override the endpoint on a method.
+    fn on<E>(self, method: Method, endpoint: E) -> Dispatcher<On<E, T>> {
+        Dispatcher {
+            endpoints: On {
+                method,
+                endpoint,
+                rest: self.endpoints,
+            },
+            meta: self.meta,
+        }
+    }
+
     impl_http_methods!(get, Method::GET);
     impl_http_methods!(post, Method::POST);
     impl_http_methods!(put, Method::PUT);
@@ -82,25 +117,89 @@ impl<S> Dispatcher<S> {
     impl_http_methods!(head, Method::HEAD);
     impl_http_methods!(trace, Method::TRACE);
     impl_http_methods!(connect, Method::CONNECT);
+
+    /// Attach metadata to the endpoint on a method, for introspection and documentation.
+    ///
+    /// ```rust
+    /// use roa::{App, Context, Endpoint, Result};
+    /// use roa::http::Method;
+    /// use roa::router::{get, Meta};
+    ///
+    /// async fn foo(ctx: &mut Context) -> Result {
+    ///     Ok(())
+    /// }
+    ///
+    /// let dispatcher = get(foo).meta(Method::GET, Meta::new().summary("Get foo"));
+    /// let methods = dispatcher.methods().unwrap();
+    /// assert_eq!(Method::GET, methods[0].0);
+    /// assert_eq!(Some("Get foo"), methods[0].1.summary.as_deref());
+    /// ```
+    pub fn meta(mut self, method: Method, meta: Meta) -> Self {
+        self.meta.insert(method, meta);
+        self
+    }
 }
 
 /// Empty dispatcher.
-impl<S> Default for Dispatcher<S> {
+impl Default for Dispatcher {
     fn default() -> Self {
-        Self(HashMap::new())
+        Self {
+            endpoints: (),
+            meta: HashMap::new(),
+        }
+    }
+}
+
+impl<'a, S> Dispatch<'a, S> for () {
+    #[inline]
+    fn contains(&self, _method: &Method) -> bool {
+        false
+    }
+
+    #[inline]
+    async fn dispatch(&'a self, ctx: &'a mut Context<S>) -> Result {
+        method_not_allowed(ctx.method())
     }
 }
 
-#[async_trait(?Send)]
-impl<'a, S> Endpoint<'a, S> for Dispatcher<S>
+impl<'a, S, E, T> Dispatch<'a, S> for On<E, T>
 where
-    S: 'static,
+    E: Endpoint<'a, S>,
+    T: Dispatch<'a, S>,
 {
     #[inline]
-    async fn call(&'a self, ctx: &'a mut Context<S>) -> Result<()> {
-        match self.0.get(ctx.method()) {
-            Some(endpoint) => endpoint.call(ctx).await,
-            None => method_not_allowed(ctx.method()),
+    fn contains(&self, method: &Method) -> bool {
+        self.method == *method || self.rest.contains(method)
+    }
+
+    #[inline]
+    async fn dispatch(&'a self, ctx: &'a mut Context<S>) -> Result {
+        if *ctx.method() == self.method {
+            self.endpoint.call(ctx).await
+        } else {
+            self.rest.dispatch(ctx).await
         }
     }
 }
+
+impl<'a, S, T> Endpoint<'a, S> for Dispatcher<T>
+where
+    T: Dispatch<'a, S>,
+{
+    #[inline]
+    fn call(&'a self, ctx: &'a mut Context<S>) -> impl 'a + Future<Output = Result> {
+        self.endpoints.dispatch(ctx)
+    }
+
+    /// Registered methods in a fixed order, with their metadata if attached.
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        let methods = ALL_METHODS
+            .iter()
+            .filter(|method| self.endpoints.contains(method))
+            .map(|method| {
+                let meta = self.meta.get(method).cloned().unwrap_or_default();
+                (method.clone(), meta)
+            });
+        Some(methods.collect())
+    }
+}
@@ -1,22 +1,9 @@
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
-use super::method_not_allowed;
+use super::{method_not_allowed, ALL_METHODS};
 use crate::http::Method;
-use crate::{async_trait, Context, Endpoint, Result};
-
-/// Methods allowed in `Guard`.
-const ALL_METHODS: [Method; 9] = [
-    Method::GET,
-    Method::POST,
-    Method::PUT,
-    Method::PATCH,
-    Method::OPTIONS,
-    Method::DELETE,
-    Method::HEAD,
-    Method::TRACE,
-    Method::CONNECT,
-];
+use crate::{Context, Endpoint, Meta, Result};
 
 /// An endpoint wrapper to guard endpoint by http method.
 pub struct Guard<E> {
@@ -67,7 +54,7 @@ pub fn allow<E>(methods: impl AsRef<[Method]>, endpoint: E) -> Guard<E> {
 /// let app = App::new().end(deny([Method::PUT, Method::DELETE], foo));
 /// ```
 pub fn deny<E>(methods: impl AsRef<[Method]>, endpoint: E) -> Guard<E> {
-    let white_list = hash_set(ALL_METHODS);
+    let white_list = hash_set(&ALL_METHODS);
     let black_list = &white_list & &hash_set(methods);
     Guard {
         endpoint,
@@ -75,7 +62,6 @@ pub fn deny<E>(methods: impl AsRef<[Method]>, endpoint: E) -> Guard<E> {
     }
 }
 
-#[async_trait(?Send)]
 impl<'a, S, E> Endpoint<'a, S> for Guard<E>
 where
     E: Endpoint<'a, S>,
@@ -88,4 +74,20 @@ where
             method_not_allowed(ctx.method())
         }
     }
+
+    /// Allowed methods of the inner endpoint, or all allowed methods if they are unknown.
+    fn methods(&self) -> Option<Vec<(Method, Meta)>> {
+        let methods = match self.endpoint.methods() {
+            Some(methods) => methods
+                .into_iter()
+                .filter(|(method, _)| self.white_list.contains(method))
+                .collect(),
+            None => ALL_METHODS
+                .iter()
+                .filter(|method| self.white_list.contains(*method))
+                .map(|method| (method.clone(), Meta::default()))
+                .collect(),
+        };
+        Some(methods)
+    }
 }
@@ -8,14 +8,32 @@ pub enum RouterError {
     /// Dynamic paths miss variable.
     MissingVariable(String),
 
+    /// Pattern is invalid, with the reason.
+    InvalidPattern(String, &'static str),
+
     /// Variables, methods or paths conflict.
     Conflict(Conflict),
+
+    /// Route name is not registered.
+    UnknownRoute(String),
+
+    /// Parameter is missing in building URL of a route.
+    MissingParam { route: String, var: String },
+
+    /// Parameter is empty or doesn't satisfy the constraint in building URL of a route.
+    InvalidParam {
+        route: String,
+        var: String,
+        value: String,
+    },
 }
 
 /// Router conflict.
 #[derive(Debug, Eq, PartialEq)]
 pub enum Conflict {
     Path(String),
+    Ambiguous(String, String),
+    Name(String),
     Method(String, http::Method),
     Variable {
         paths: (String, String),
@@ -27,6 +45,10 @@ impl Display for Conflict {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
             Conflict::Path(path) => f.write_str(&format!("conflict path: `{}`", path)),
+            Conflict::Ambiguous(existing, path) => {
+                f.write_str(&format!("ambiguous paths: `{}` and `{}`", existing, path))
+            }
+            Conflict::Name(name) => f.write_str(&format!("conflict route name: `{}`", name)),
             Conflict::Method(path, method) => f.write_str(&format!(
                 "conflict method: `{}` on `{}` is already set",
                 method, path
@@ -46,6 +68,17 @@ impl Display for RouterError {
             RouterError::MissingVariable(path) => {
                 f.write_str(&format!("missing variable on path {}", path))
             }
+            RouterError::InvalidPattern(path, reason) => {
+                f.write_str(&format!("invalid pattern `{}`: {}", path, reason))
+            }
+            RouterError::UnknownRoute(name) => f.write_str(&format!("unknown route `{}`", name)),
+            RouterError::MissingParam { route, var } => {
+                f.write_str(&format!("missing parameter `{}` of route `{}`", var, route))
+            }
+            RouterError::InvalidParam { route, var, value } => f.write_str(&format!(
+                "invalid parameter `{}` of route `{}`: `{}`",
+                var, route, value
+            )),
         }
     }
 }
@@ -75,6 +108,10 @@ mod tests {
             "conflict method: `GET` on `/` is already set",
             Conflict::Method("/".to_string(), http::Method::GET).to_string()
         );
+        assert_eq!(
+            "ambiguous paths: `/user/:id` and `/user/:name`",
+            Conflict::Ambiguous("/user/:id".to_string(), "/user/:name".to_string()).to_string()
+        );
         assert_eq!(
             "conflict variable `id`: between `/:id` and `/user/:id`",
             Conflict::Variable {
@@ -95,5 +132,25 @@ mod tests {
             "missing variable on path /:",
             RouterError::MissingVariable("/:".to_string()).to_string()
         );
+        assert_eq!(
+            "invalid pattern `/*{a}*{b}`: a segment can have only one wildcard",
+            RouterError::InvalidPattern(
+                "/*{a}*{b}".to_string(),
+                "a segment can have only one wildcard"
+            )
+            .to_string()
+        );
+        assert_eq!(
+            "unknown route `user`",
+            RouterError::UnknownRoute("user".to_string()).to_string()
+        );
+        assert_eq!(
+            "missing parameter `id` of route `user`",
+            RouterError::MissingParam {
+                route: "user".to_string(),
+                var: "id".to_string()
+            }
+            .to_string()
+        );
     }
 }
@@ -0,0 +1,17 @@
+use crate::http::Method;
+use crate::Meta;
+
+/// A route registered in `RouteTable`, listed by `RouteTable::iter`.
+#[derive(Debug, Clone)]
+pub struct RouteInfo {
+    /// The route pattern, like `/user/:id`.
+    pub pattern: String,
+
+    /// The name of route, if it's registered by `Router::named`.
+    pub name: Option<String>,
+
+    /// Methods and their metadata, listed by `Endpoint::methods`,
+    /// like those of a `Dispatcher` or a `Guard`.
+    /// `None` means the methods are unknown and any method may be accepted.
+    pub methods: Option<Vec<(Method, Meta)>>,
+}
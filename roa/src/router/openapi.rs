@@ -0,0 +1,447 @@
+//! This module provides a generator of [OpenAPI 3](https://spec.openapis.org/oas/v3.0.3)
+//! documents from routes of `RouteTable`, and an endpoint `Spec` to serve the document.
+//!
+//! Only routes whose methods are known by `Endpoint::methods` are documented,
+//! like those of a `Dispatcher` or a `Guard`, with metadata attached by `Dispatcher::meta`.
+//! JSON schemas of request and responses are attached to `Meta` as `Schemas`.
+//! Variables are documented as path parameters, with schemas inferred from their constraints.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::router::openapi::{OpenApi, Schemas};
+//! use roa::router::{get, Meta, Router};
+//! use roa::http::{Method, StatusCode};
+//! use roa::test::TestClient;
+//! use roa::{App, Context, EndpointExt};
+//! use serde_json::json;
+//!
+//! async fn query(ctx: &mut Context) -> roa::Result {
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let meta = Meta::new().summary("Get a user").with(Schemas::new().response(
+//!         StatusCode::OK,
+//!         "the user",
+//!         Some(json!({"type": "object"})),
+//!     ));
+//!     let api = Router::new()
+//!         .on("/user/:id<u64>", get(query).meta(Method::GET, meta))
+//!         .routes("/api")?;
+//!     let spec = OpenApi::new("Users", "1.0.0").spec(api.iter());
+//!     let docs = Router::new().on("/openapi.json", get(spec)).routes("/")?;
+//!     let client = TestClient::new(App::new().end(api.or(docs)));
+//!
+//!     let resp = client.get("/openapi.json").send().await;
+//!     resp.assert_status(StatusCode::OK);
+//!     let document: serde_json::Value = resp.json().await?;
+//!     assert_eq!("Get a user", document["paths"]["/api/user/{id}"]["get"]["summary"]);
+//!     Ok(())
+//! }
+//! ```
+
+use bytes::Bytes;
+use headers::{ContentType, HeaderMapExt};
+use serde_json::{json, Map, Value};
+
+use super::path::{Constraint, Pattern, Segment};
+use super::{Meta, RouteInfo};
+use crate::http::{Method, StatusCode};
+use crate::{Context, Endpoint, Result};
+
+/// The version of OpenAPI specification.
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// JSON schemas of an operation, attached to `Meta` by `Meta::with`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Schemas {
+    /// The JSON schema of request body.
+    pub request: Option<Value>,
+
+    /// Responses, with status, description and an optional JSON schema of body.
+    pub responses: Vec<(StatusCode, String, Option<Value>)>,
+}
+
+impl Schemas {
+    /// Construct empty schemas.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the JSON schema of request body.
+    pub fn request(mut self, schema: Value) -> Self {
+        self.request = Some(schema);
+        self
+    }
+
+    /// Add a response with a description and an optional JSON schema of body.
+    pub fn response(
+        mut self,
+        status: StatusCode,
+        description: impl ToString,
+        schema: Option<Value>,
+    ) -> Self {
+        self.responses
+            .push((status, description.to_string(), schema));
+        self
+    }
+}
+
+/// A generator of OpenAPI documents.
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    servers: Vec<String>,
+}
+
+impl OpenApi {
+    /// Construct a generator with the title and version of API.
+    pub fn new(title: impl ToString, version: impl ToString) -> Self {
+        Self {
+            title: title.to_string(),
+            version: version.to_string(),
+            description: None,
+            servers: Vec::new(),
+        }
+    }
+
+    /// Set the description of API.
+    pub fn description(mut self, description: impl ToString) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Add a server URL.
+    pub fn server(mut self, url: impl ToString) -> Self {
+        self.servers.push(url.to_string());
+        self
+    }
+
+    /// Generate a document from routes.
+    pub fn document<'a>(&self, routes: impl IntoIterator<Item = &'a RouteInfo>) -> Value {
+        let mut info = json!({"title": self.title, "version": self.version});
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+        let mut paths = Map::new();
+        for route in routes {
+            let methods = match &route.methods {
+                Some(methods) => methods,
+                None => continue,
+            };
+            let pattern: Pattern = match route.pattern.parse() {
+                Ok(pattern) => pattern,
+                Err(_) => continue,
+            };
+            let item = paths
+                .entry(path_template(&pattern))
+                .or_insert_with(|| json!({}));
+            let parameters = path_parameters(&pattern);
+            if !parameters.is_empty() {
+                item["parameters"] = Value::Array(parameters);
+            }
+            for (method, meta) in methods {
+                if let Some(key) = operation_key(method) {
+                    let operation_id = meta.operation_id.clone().or_else(|| {
+                        route.name.as_ref().map(|name| match methods.len() {
+                            1 => name.clone(),
+                            _ => format!("{}_{}", name, key),
+                        })
+                    });
+                    item[key] = operation(meta, operation_id);
+                }
+            }
+        }
+        let mut document = json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+        });
+        if !self.servers.is_empty() {
+            document["servers"] = self
+                .servers
+                .iter()
+                .map(|url| json!({ "url": url }))
+                .collect();
+        }
+        document
+    }
+
+    /// Generate a document from routes, and construct an endpoint to serve it.
+    pub fn spec<'a>(&self, routes: impl IntoIterator<Item = &'a RouteInfo>) -> Spec {
+        Spec(Bytes::from(self.document(routes).to_string()))
+    }
+}
+
+/// An endpoint to serve an OpenAPI document as "application/json".
+#[derive(Debug, Clone)]
+pub struct Spec(Bytes);
+
+impl<'a, S> Endpoint<'a, S> for Spec {
+    #[inline]
+    async fn call(&'a self, ctx: &'a mut Context<S>) -> Result {
+        ctx.resp.write(self.0.clone());
+        ctx.resp.headers.typed_insert(ContentType::json());
+        Ok(())
+    }
+}
+
+/// Format the path template of pattern, like `/user/{id}`.
+fn path_template(pattern: &Pattern) -> String {
+    if pattern.segments.is_empty() {
+        return "/".to_string();
+    }
+    let mut template = String::new();
+    for segment in pattern.segments.iter() {
+        template.push('/');
+        match segment {
+            Segment::Static(segment) => template.push_str(segment),
+            Segment::Param { name, .. } => template.push_str(&format!("{{{}}}", name)),
+            Segment::Wildcard {
+                prefix,
+                name,
+                suffix,
+                ..
+            } => template.push_str(&format!("{}{{{}}}{}", prefix, name, suffix)),
+        }
+    }
+    template
+}
+
+/// Describe variables of pattern as path parameters.
+fn path_parameters(pattern: &Pattern) -> Vec<Value> {
+    pattern
+        .segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Static(_) => None,
+            Segment::Param { name, constraint }
+            | Segment::Wildcard {
+                name, constraint, ..
+            } => Some(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": schema(constraint.as_ref()),
+            })),
+        })
+        .collect()
+}
+
+/// Infer the schema of a variable from its constraint.
+fn schema(constraint: Option<&Constraint>) -> Value {
+    match constraint {
+        None => json!({"type": "string"}),
+        Some(Constraint::Regex(regex)) => json!({"type": "string", "pattern": regex.as_str()}),
+        Some(Constraint::Type(ty, _)) => match *ty {
+            "i8" | "i16" | "i32" => json!({"type": "integer", "format": "int32"}),
+            "i64" | "isize" => json!({"type": "integer", "format": "int64"}),
+            "u8" | "u16" => json!({"type": "integer", "format": "int32", "minimum": 0}),
+            "u32" | "u64" | "usize" => json!({"type": "integer", "format": "int64", "minimum": 0}),
+            "i128" => json!({"type": "integer"}),
+            "u128" => json!({"type": "integer", "minimum": 0}),
+            "f32" => json!({"type": "number", "format": "float"}),
+            "f64" => json!({"type": "number", "format": "double"}),
+            "bool" => json!({"type": "boolean"}),
+            "uuid" => json!({"type": "string", "format": "uuid"}),
+            _ => json!({"type": "string"}),
+        },
+    }
+}
+
+/// The key of operation in a path item, `None` if the method is not supported by OpenAPI.
+fn operation_key(method: &Method) -> Option<&'static str> {
+    match *method {
+        Method::GET => Some("get"),
+        Method::POST => Some("post"),
+        Method::PUT => Some("put"),
+        Method::PATCH => Some("patch"),
+        Method::DELETE => Some("delete"),
+        Method::HEAD => Some("head"),
+        Method::OPTIONS => Some("options"),
+        Method::TRACE => Some("trace"),
+        _ => None,
+    }
+}
+
+/// Describe an operation by metadata.
+fn operation(meta: &Meta, operation_id: Option<String>) -> Value {
+    let mut operation = Map::new();
+    if let Some(summary) = &meta.summary {
+        operation.insert("summary".into(), json!(summary));
+    }
+    if let Some(description) = &meta.description {
+        operation.insert("description".into(), json!(description));
+    }
+    if let Some(operation_id) = operation_id {
+        operation.insert("operationId".into(), json!(operation_id));
+    }
+    if !meta.tags.is_empty() {
+        operation.insert("tags".into(), json!(meta.tags));
+    }
+    let schemas = meta.get::<Schemas>();
+    if let Some(schema) = schemas.and_then(|schemas| schemas.request.as_ref()) {
+        operation.insert(
+            "requestBody".into(),
+            json!({
+                "required": true,
+                "content": {"application/json": {"schema": schema}},
+            }),
+        );
+    }
+    let mut responses = Map::new();
+    for (status, description, schema) in schemas.iter().flat_map(|schemas| &schemas.responses) {
+        let mut response = json!({ "description": description });
+        if let Some(schema) = schema {
+            response["content"] = json!({"application/json": {"schema": schema}});
+        }
+        responses.insert(status.as_str().to_string(), response);
+    }
+    if responses.is_empty() {
+        responses.insert("default".into(), json!({"description": "default response"}));
+    }
+    operation.insert("responses".into(), Value::Object(responses));
+    Value::Object(operation)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{OpenApi, Schemas};
+    use crate::http::{Method, StatusCode};
+    use crate::router::{get, post, Meta, Router};
+    use crate::test::TestClient;
+    use crate::{App, Context, EndpointExt};
+
+    async fn end(_ctx: &mut Context) -> crate::Result {
+        Ok(())
+    }
+
+    fn api() -> Result<crate::router::RouteTable<()>, crate::router::RouterError> {
+        let create = Meta::new().summary("Create a user").tag("user").with(
+            Schemas::new().request(json!({"type": "object"})).response(
+                StatusCode::CREATED,
+                "created",
+                None,
+            ),
+        );
+        Router::new()
+            .named(
+                "users",
+                "/users",
+                get(end).post(end).meta(Method::POST, create),
+            )
+            .named("user", r"/users/:id<u64>/*{path(\w+)}", get(end))
+            .on("/graphql", end)
+            .routes("/api")
+    }
+
+    #[test]
+    fn document() -> Result<(), Box<dyn std::error::Error>> {
+        let document = OpenApi::new("Users", "1.0.0")
+            .description("user service")
+            .server("http://localhost:8000")
+            .document(api()?.iter());
+        assert_eq!("3.0.3", document["openapi"]);
+        assert_eq!(
+            json!({"title": "Users", "version": "1.0.0", "description": "user service"}),
+            document["info"]
+        );
+        assert_eq!(
+            json!([{"url": "http://localhost:8000"}]),
+            document["servers"]
+        );
+        assert_eq!(2, document["paths"].as_object().unwrap().len());
+
+        let users = &document["paths"]["/api/users"];
+        assert_eq!("users_get", users["get"]["operationId"]);
+        assert_eq!(
+            json!({"default": {"description": "default response"}}),
+            users["get"]["responses"]
+        );
+        assert_eq!(
+            json!({
+                "summary": "Create a user",
+                "operationId": "users_post",
+                "tags": ["user"],
+                "requestBody": {
+                    "required": true,
+                    "content": {"application/json": {"schema": {"type": "object"}}},
+                },
+                "responses": {"201": {"description": "created"}},
+            }),
+            users["post"]
+        );
+
+        let user = &document["paths"]["/api/users/{id}/{path}"];
+        assert_eq!("user", user["get"]["operationId"]);
+        assert_eq!(
+            json!([
+                {
+                    "name": "id",
+                    "in": "path",
+                    "required": true,
+                    "schema": {"type": "integer", "format": "int64", "minimum": 0},
+                },
+                {
+                    "name": "path",
+                    "in": "path",
+                    "required": true,
+                    "schema": {"type": "string", "pattern": r"^(?:\w+)$"},
+                },
+            ]),
+            user["parameters"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spec() -> Result<(), Box<dyn std::error::Error>> {
+        let api = api()?;
+        let spec = OpenApi::new("Users", "1.0.0").spec(api.iter());
+        let docs = Router::new().on("/openapi.json", get(spec)).routes("/")?;
+        let client = TestClient::new(App::new().end(api.or(docs)));
+        let resp = client.get("/openapi.json").send().await;
+        resp.assert_status(StatusCode::OK)
+            .assert_header("content-type", "application/json");
+        let document: serde_json::Value = resp.json().await?;
+        assert_eq!("Users", document["info"]["title"]);
+
+        client
+            .post("/openapi.json")
+            .send()
+            .await
+            .assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        client
+            .post("/api/users")
+            .send()
+            .await
+            .assert_status(StatusCode::OK);
+        Ok(())
+    }
+
+    #[test]
+    fn skip_unknown_methods() -> Result<(), Box<dyn std::error::Error>> {
+        let api = Router::new()
+            .on("/", post(end))
+            .on("/any", end)
+            .routes("/")?;
+        let document = OpenApi::new("Any", "0.1.0").document(api.iter());
+        assert_eq!(
+            json!(["/"]),
+            json!(document["paths"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>())
+        );
+        assert!(document.get("servers").is_none());
+        Ok(())
+    }
+}
@@ -2,21 +2,10 @@ use std::collections::HashSet;
 use std::convert::AsRef;
 use std::str::FromStr;
 
-use regex::{escape, Captures, Regex};
+use regex::Regex;
 
 use super::{Conflict, RouterError};
 
-/// Match pattern *{variable}
-const WILDCARD: &str = r"\*\{(?P<var>\w*)\}";
-
-/// Match pattern /:variable/
-const VARIABLE: &str = r"/:(?P<var>\w*)/";
-
-/// {/path path/ /path/} => /path/
-pub fn standardize_path(raw_path: &str) -> String {
-    format!("/{}/", raw_path.trim_matches('/'))
-}
-
 /// Join multiple segments.
 pub fn join_path<'a>(paths: impl 'a + AsRef<[&'a str]>) -> String {
     paths
@@ -28,99 +17,287 @@ pub fn join_path<'a>(paths: impl 'a + AsRef<[&'a str]>) -> String {
         .join("/")
 }
 
-/// Build pattern.
-fn must_build(pattern: &str) -> Regex {
-    Regex::new(pattern).unwrap_or_else(|err| {
-        panic!(
-            r#"{}
-                regex pattern {} is invalid, this is a bug of roa::router::path.
-                please report it to https://github.com/Hexilee/roa"#,
-            err, pattern
-        )
-    })
+/// Split a path into segments, the root path has no segment.
+pub fn segments(path: &str) -> Vec<&str> {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        Vec::new()
+    } else {
+        path.split('/').collect()
+    }
 }
 
-/// Parsed path.
-#[derive(Clone)]
-pub enum Path {
+/// A segment of pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A static segment, like `user`.
     Static(String),
-    Dynamic(RegexPath),
+
+    /// A variable matching one segment, with an optional constraint,
+    /// like `:id`, `:id(\d+)` or `:id<u64>`.
+    Param {
+        name: String,
+        constraint: Option<Constraint>,
+    },
+
+    /// A variable matching one or more segments, with optional static prefix and suffix,
+    /// and an optional constraint, like `*{path}`, `*{file}.h` or `*{path(\w+/\w+)}`.
+    Wildcard {
+        prefix: String,
+        name: String,
+        suffix: String,
+        constraint: Option<Constraint>,
+    },
+}
+
+/// A constraint on values of a variable.
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    /// A regular expression matching the whole value, like `(\d+)`.
+    Regex(Regex),
+
+    /// A predefined type, like `<u64>` or `<uuid>`.
+    Type(&'static str, Check),
 }
 
-/// Dynamic path.
-#[derive(Clone)]
-pub struct RegexPath {
+/// A function checking values of a predefined type.
+type Check = fn(&str) -> bool;
+
+impl Constraint {
+    /// Predefined types.
+    const TYPES: &'static [(&'static str, Check)] = &[
+        ("i8", |value| value.parse::<i8>().is_ok()),
+        ("i16", |value| value.parse::<i16>().is_ok()),
+        ("i32", |value| value.parse::<i32>().is_ok()),
+        ("i64", |value| value.parse::<i64>().is_ok()),
+        ("i128", |value| value.parse::<i128>().is_ok()),
+        ("isize", |value| value.parse::<isize>().is_ok()),
+        ("u8", |value| value.parse::<u8>().is_ok()),
+        ("u16", |value| value.parse::<u16>().is_ok()),
+        ("u32", |value| value.parse::<u32>().is_ok()),
+        ("u64", |value| value.parse::<u64>().is_ok()),
+        ("u128", |value| value.parse::<u128>().is_ok()),
+        ("usize", |value| value.parse::<usize>().is_ok()),
+        ("f32", |value| value.parse::<f32>().is_ok()),
+        ("f64", |value| value.parse::<f64>().is_ok()),
+        ("bool", |value| value.parse::<bool>().is_ok()),
+        ("uuid", is_uuid),
+    ];
+
+    /// Parse a regex constraint, matching the whole value.
+    fn regex(path: &str, source: &str) -> Result<Self, RouterError> {
+        Regex::new(&format!("^(?:{})$", source))
+            .map(Constraint::Regex)
+            .map_err(|_| RouterError::InvalidPattern(path.to_string(), "invalid regex constraint"))
+    }
+
+    /// Parse a predefined type constraint.
+    fn ty(path: &str, name: &str) -> Result<Self, RouterError> {
+        Self::TYPES
+            .iter()
+            .find(|(ty, _)| *ty == name)
+            .map(|(ty, check)| Constraint::Type(ty, *check))
+            .ok_or_else(|| RouterError::InvalidPattern(path.to_string(), "unknown type constraint"))
+    }
+
+    /// Whether the value satisfies this constraint.
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Constraint::Regex(regex) => regex.is_match(value),
+            Constraint::Type(_, check) => check(value),
+        }
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Constraint::Regex(this), Constraint::Regex(other)) => this.as_str() == other.as_str(),
+            (Constraint::Type(this, _), Constraint::Type(other, _)) => this == other,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constraint {}
+
+/// Whether a value is a UUID in the hyphenated form, like `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(index, c)| match index {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// A parsed route pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    /// The standardized pattern without trailing slash, like `/user/:id`.
     pub raw: String,
-    pub vars: HashSet<String>,
-    pub re: Regex,
+    pub segments: Vec<Segment>,
 }
 
-impl FromStr for Path {
-    type Err = RouterError;
-    fn from_str(raw_path: &str) -> Result<Self, Self::Err> {
-        let path = standardize_path(raw_path);
-        Ok(match path_to_regexp(&path)? {
-            None => Path::Static(path),
-            Some((pattern, vars)) => Path::Dynamic(RegexPath {
-                raw: path,
-                vars,
-                re: must_build(&format!(r"^{}$", pattern)),
-            }),
+impl Pattern {
+    /// Names of variables in order.
+    pub fn vars(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Static(_) => None,
+            Segment::Param { name, .. } | Segment::Wildcard { name, .. } => Some(name.as_str()),
         })
     }
 }
 
-fn path_to_regexp(path: &str) -> Result<Option<(String, HashSet<String>)>, RouterError> {
-    let mut pattern = escape(path);
-    let mut vars = HashSet::new();
-    let wildcard_re = must_build(WILDCARD);
-    let variable_re = must_build(VARIABLE);
-    let wildcards: Vec<Captures> = wildcard_re.captures_iter(path).collect();
-    let variable_template = path.replace('/', "//"); // to match continuous variables like /:year/:month/:day/
-    let variables: Vec<Captures> = variable_re.captures_iter(&variable_template).collect();
-    if wildcards.is_empty() && variables.is_empty() {
-        Ok(None)
-    } else {
-        // detect variable conflicts.
-        let try_add_variable = |set: &mut HashSet<String>, variable: String| {
-            if set.insert(variable.clone()) {
-                Ok(())
-            } else {
-                Err(Conflict::Variable {
-                    paths: (path.to_string(), path.to_string()),
-                    var_name: variable,
-                })
+/// Split a pattern into segments, slashes in constraints are not separators.
+fn split_pattern(pattern: &str) -> Vec<&str> {
+    let pattern = pattern.trim_matches('/');
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, c) in pattern.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '/' if depth == 0 => {
+                segments.push(&pattern[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    segments.push(&pattern[start..]);
+    segments
+}
+
+/// Find the end of a regex constraint starting with `(`, return the index of closing `)`.
+fn regex_end(constraint: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for (index, c) in constraint.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
             }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// A variable with an optional constraint, and the rest of segment.
+struct Variable<'a> {
+    name: &'a str,
+    constraint: Option<Constraint>,
+    rest: &'a str,
+}
+
+impl<'a> Variable<'a> {
+    /// Parse a variable name like `\w*`, followed by an optional constraint.
+    fn parse(path: &str, segment: &'a str) -> Result<Self, RouterError> {
+        let name_len = segment
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(segment.len());
+        let (name, rest) = segment.split_at(name_len);
+        let (constraint, rest) = if rest.starts_with('(') {
+            let end = regex_end(rest).ok_or_else(|| {
+                RouterError::InvalidPattern(path.to_string(), "unclosed regex constraint")
+            })?;
+            (
+                Some(Constraint::regex(path, &rest[1..end])?),
+                &rest[end + 1..],
+            )
+        } else if let Some(ty) = rest.strip_prefix('<') {
+            let end = ty.find('>').ok_or_else(|| {
+                RouterError::InvalidPattern(path.to_string(), "unclosed type constraint")
+            })?;
+            (Some(Constraint::ty(path, &ty[..end])?), &ty[end + 1..])
+        } else {
+            (None, rest)
         };
+        if name.is_empty() && constraint.is_some() {
+            return Err(RouterError::MissingVariable(path.to_string()));
+        }
+        Ok(Self {
+            name,
+            constraint,
+            rest,
+        })
+    }
+}
 
-        // match wildcard patterns
-        for cap in wildcards {
-            let variable = &cap["var"];
-            if variable.is_empty() {
+impl Segment {
+    fn parse(path: &str, segment: &str) -> Result<Self, RouterError> {
+        if let Some(var) = segment.strip_prefix(':') {
+            if var.is_empty() {
                 return Err(RouterError::MissingVariable(path.to_string()));
             }
-            let var = escape(variable);
-            pattern = pattern.replace(
-                &escape(&format!(r"*{{{}}}", variable)),
-                &format!(r"(?P<{}>\S+)", &var),
-            );
-            try_add_variable(&mut vars, var)?;
+            let var = Variable::parse(path, var)?;
+            return Ok(if !var.name.is_empty() && var.rest.is_empty() {
+                Segment::Param {
+                    name: var.name.to_string(),
+                    constraint: var.constraint,
+                }
+            } else {
+                Segment::Static(segment.to_string())
+            });
         }
+        if let Some(start) = segment.find("*{") {
+            let var = Variable::parse(path, &segment[start + 2..])?;
+            if let Some(suffix) = var.rest.strip_prefix('}') {
+                if var.name.is_empty() {
+                    return Err(RouterError::MissingVariable(path.to_string()));
+                }
+                if suffix.contains("*{") {
+                    return Err(RouterError::InvalidPattern(
+                        path.to_string(),
+                        "a segment can have only one wildcard",
+                    ));
+                }
+                return Ok(Segment::Wildcard {
+                    prefix: segment[..start].to_string(),
+                    name: var.name.to_string(),
+                    suffix: suffix.to_string(),
+                    constraint: var.constraint,
+                });
+            }
+        }
+        Ok(Segment::Static(segment.to_string()))
+    }
+}
 
-        // match segment variable patterns
-        for cap in variables {
-            let variable = &cap["var"];
-            if variable.is_empty() {
-                return Err(RouterError::MissingVariable(path.to_string()));
+impl FromStr for Pattern {
+    type Err = RouterError;
+    fn from_str(raw_path: &str) -> Result<Self, Self::Err> {
+        let raw = format!("/{}", raw_path.trim_matches('/'));
+        let segments = split_pattern(&raw)
+            .into_iter()
+            .map(|segment| Segment::parse(&raw, segment))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pattern = Self { raw, segments };
+        // detect variable conflicts.
+        let mut vars = HashSet::new();
+        for var in pattern.vars() {
+            if !vars.insert(var) {
+                return Err(Conflict::Variable {
+                    paths: (pattern.raw.clone(), pattern.raw.clone()),
+                    var_name: var.to_string(),
+                }
+                .into());
             }
-            let var = escape(variable);
-            pattern = pattern.replace(
-                &escape(&format!(r":{}", variable)),
-                &format!(r"(?P<{}>[^\s/]+)", &var),
-            );
-            try_add_variable(&mut vars, var)?;
         }
-        Ok(Some((pattern, vars)))
+        Ok(pattern)
     }
 }
 
@@ -128,120 +305,130 @@ fn path_to_regexp(path: &str) -> Result<Option<(String, HashSet<String>)>, Route
 mod tests {
     use test_case::test_case;
 
-    use super::{must_build, path_to_regexp, Path, VARIABLE, WILDCARD};
-
-    #[test_case("/:id/"; "pure dynamic")]
-    #[test_case("/user/:id/"; "static prefix")]
-    #[test_case("/user/:id/name"; "static prefix and suffix")]
-    fn var_regex_match(path: &str) {
-        let re = must_build(VARIABLE);
-        let cap = re.captures(path);
-        assert!(cap.is_some());
-        assert_eq!("id", &cap.unwrap()["var"]);
-    }
-
-    #[test_case("/-:id/"; "invalid prefix")]
-    #[test_case("/:i-d/"; "invalid variable name")]
-    #[test_case("/:id-/"; "invalid suffix")]
-    fn var_regex_mismatch(path: &str) {
-        let re = must_build(VARIABLE);
-        let cap = re.captures(path);
-        assert!(cap.is_none());
-    }
-
-    #[test_case("*{id}"; "pure dynamic")]
-    #[test_case("user-*{id}"; "static prefix")]
-    #[test_case("user-*{id}-name"; "static prefix and suffix")]
-    fn wildcard_regex_match(path: &str) {
-        let re = must_build(WILDCARD);
-        let cap = re.captures(path);
-        assert!(cap.is_some());
-        assert_eq!("id", &cap.unwrap()["var"]);
-    }
+    use super::{segments, split_pattern, Constraint, Pattern, Segment};
 
-    #[test_case("*"; "no variable")]
-    #[test_case("*{-id}"; "invalid variable name")]
-    fn wildcard_regex_mismatch(path: &str) {
-        let re = must_build(WILDCARD);
-        let cap = re.captures(path);
-        assert!(cap.is_none());
+    fn param(name: &str) -> Segment {
+        Segment::Param {
+            name: name.to_string(),
+            constraint: None,
+        }
     }
 
-    #[test_case(r"/:id/" => r"/(?P<id>[^\s/]+)/"; "single variable")]
-    #[test_case(r"/:year/:month/:day/" => r"/(?P<year>[^\s/]+)/(?P<month>[^\s/]+)/(?P<day>[^\s/]+)/"; "multiple variable")]
-    #[test_case(r"*{id}" => r"(?P<id>\S+)"; "single wildcard")]
-    #[test_case(r"*{year}_*{month}_*{day}" => r"(?P<year>\S+)_(?P<month>\S+)_(?P<day>\S+)"; "multiple wildcard")]
-    fn path_to_regexp_dynamic_pattern(path: &str) -> String {
-        path_to_regexp(path).unwrap().unwrap().0
+    fn wildcard(prefix: &str, name: &str, suffix: &str) -> Segment {
+        Segment::Wildcard {
+            prefix: prefix.to_string(),
+            name: name.to_string(),
+            suffix: suffix.to_string(),
+            constraint: None,
+        }
     }
 
-    #[test_case(r"/id/")]
-    #[test_case(r"/user/post/")]
-    fn path_to_regexp_static(path: &str) {
-        assert!(path_to_regexp(path).unwrap().is_none())
+    fn constraint(path: &str) -> Constraint {
+        match path.parse::<Pattern>().unwrap().segments.pop() {
+            Some(Segment::Param {
+                constraint: Some(constraint),
+                ..
+            })
+            | Some(Segment::Wildcard {
+                constraint: Some(constraint),
+                ..
+            }) => constraint,
+            segment => panic!("`{}` has no constraint: {:?}", path, segment),
+        }
     }
 
-    #[test_case(r"/:/"; "missing variable name")]
-    #[test_case(r"*{}"; "wildcard missing variable name")]
-    #[test_case(r"/:id/:id/"; "conflict variable")]
-    #[test_case(r"*{id}-*{id}"; "wildcard conflict variable")]
-    #[test_case(r"/:id/*{id}"; "mix conflict variable")]
-    fn path_to_regexp_err(path: &str) {
-        assert!(path_to_regexp(path).is_err())
+    fn static_(segment: &str) -> Segment {
+        Segment::Static(segment.to_string())
     }
 
-    fn path_match(pattern: &str, path: &str) {
-        let pattern: Path = pattern.parse().unwrap();
-        match pattern {
-            Path::Static(pattern) => panic!("`{}` should be dynamic", pattern),
-            Path::Dynamic(re) => assert!(re.re.is_match(path)),
-        }
+    #[test_case("/" => Vec::<String>::new(); "root")]
+    #[test_case("/user/1/" => vec!["user", "1"]; "trailing slash")]
+    #[test_case("user//1" => vec!["user", "", "1"]; "empty segment")]
+    fn split(path: &str) -> Vec<String> {
+        segments(path)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
     }
 
-    fn path_not_match(pattern: &str, path: &str) {
-        let pattern: Path = pattern.parse().unwrap();
-        match pattern {
-            Path::Static(pattern) => panic!("`{}` should be dynamic", pattern),
-            Path::Dynamic(re) => {
-                println!("regex: {}", re.re.to_string());
-                assert!(!re.re.is_match(path))
-            }
-        }
+    #[test_case("/:id/" => vec![param("id")]; "single variable")]
+    #[test_case("/:year/:month/:day" => vec![param("year"), param("month"), param("day")]; "multiple variable")]
+    #[test_case("/user/:id/name" => vec![static_("user"), param("id"), static_("name")]; "static prefix and suffix")]
+    #[test_case("*{id}" => vec![wildcard("", "id", "")]; "single wildcard")]
+    #[test_case("/usr/include/*{dir}/*{file}.h" => vec![static_("usr"), static_("include"), wildcard("", "dir", ""), wildcard("", "file", ".h")]; "segment wildcard")]
+    #[test_case("user-*{id}-name" => vec![wildcard("user-", "id", "-name")]; "wildcard prefix and suffix")]
+    fn parse_dynamic(path: &str) -> Vec<Segment> {
+        path.parse::<Pattern>().unwrap().segments
     }
 
-    #[test_case(r"/user/1/")]
-    #[test_case(r"/user/65535/")]
-    fn single_variable_path_match(path: &str) {
-        path_match(r"/user/:id", path)
+    #[test_case("/-:id/"; "invalid prefix")]
+    #[test_case("/:i-d/"; "invalid variable name")]
+    #[test_case("/:id-/"; "invalid suffix")]
+    #[test_case("*"; "no wildcard variable")]
+    #[test_case("*{-id}"; "invalid wildcard name")]
+    #[test_case("/user/post/"; "static path")]
+    fn parse_static(path: &str) {
+        let pattern: Pattern = path.parse().unwrap();
+        assert!(pattern
+            .segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Static(_))));
     }
 
-    #[test_case(r"/2000/01/01/")]
-    #[test_case(r"/2020/02/20/")]
-    fn multiple_variable_path_match(path: &str) {
-        path_match(r"/:year/:month/:day", path)
+    #[test_case(r"/user/:id(\d+)/posts" => vec!["user", r":id(\d+)", "posts"]; "regex")]
+    #[test_case(r"/files/*{path(\w+/\w+)}" => vec!["files", r"*{path(\w+/\w+)}"]; "slash in regex")]
+    #[test_case(r"/files/*{path(\(/\))}" => vec!["files", r"*{path(\(/\))}"]; "escaped parenthesis")]
+    fn split_constraint(path: &str) -> Vec<String> {
+        split_pattern(path)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
     }
 
-    #[test_case(r"/usr/include/boost/boost.h/")]
-    #[test_case(r"/usr/include/uv/uv.h/")]
-    fn segment_wildcard_path_match(path: &str) {
-        path_match(r"/usr/include/*{dir}/*{file}.h", path)
+    #[test_case(r"/:id(\d+)", "123" => true)]
+    #[test_case(r"/:id(\d+)", "12a" => false; "regex matches the whole value")]
+    #[test_case(r"/:id(a|b)", "ab" => false; "alternation matches the whole value")]
+    #[test_case(r"/:id(\d{2})", "12" => true; "regex with braces")]
+    #[test_case(r"*{path(\w+/\w+)}", "a/b" => true)]
+    #[test_case(r"*{path(\w+/\w+)}", "a/b/c" => false)]
+    #[test_case("/:id<u64>", "65535" => true)]
+    #[test_case("/:id<u64>", "-1" => false)]
+    #[test_case("/:id<i8>", "-128" => true)]
+    #[test_case("/:id<i8>", "128" => false)]
+    #[test_case("/:flag<bool>", "true" => true)]
+    #[test_case("/:slug<uuid>", "67e55044-10b1-426f-9247-bb680e5fe0c8" => true)]
+    #[test_case("/:slug<uuid>", "67e55044-10b1-426f-9247-bb680e5fe0c" => false)]
+    #[test_case("/:slug<uuid>", "67e55044x10b1-426f-9247-bb680e5fe0c8" => false)]
+    fn check_constraint(path: &str, value: &str) -> bool {
+        constraint(path).matches(value)
     }
 
-    #[test_case(r"/srv/static/app/index.html/")]
-    #[test_case(r"/srv/static/../../index.html/")]
-    fn full_wildcard_path_match(path: &str) {
-        path_match(r"/srv/static/*{path}/", path)
+    #[test_case(r"/:id(\d+)/" => (r"/:id(\d+)".to_string(), vec!["id".to_string()]); "regex")]
+    #[test_case(r"*{file<uuid>}.h" => ("/*{file<uuid>}.h".to_string(), vec!["file".to_string()]); "wildcard type")]
+    fn parse_constraint(path: &str) -> (String, Vec<String>) {
+        let pattern: Pattern = path.parse().unwrap();
+        let vars = pattern.vars().map(ToString::to_string).collect();
+        (pattern.raw, vars)
     }
 
-    #[test_case(r"/srv/app/index.html/")]
-    #[test_case(r"/srv/../../index.html/")]
-    fn variable_path_not_match(path: &str) {
-        path_not_match(r"/srv/:path/", path)
+    #[test_case("/user/:id/" => "/user/:id"; "trailing slash")]
+    #[test_case("" => "/"; "root")]
+    fn raw(path: &str) -> String {
+        path.parse::<Pattern>().unwrap().raw
     }
 
-    #[should_panic]
-    #[test]
-    fn must_build_fails() {
-        must_build(r"{");
+    #[test_case(r"/:/"; "missing variable name")]
+    #[test_case(r"*{}"; "wildcard missing variable name")]
+    #[test_case(r"/:id/:id/"; "conflict variable")]
+    #[test_case(r"*{id}/*{id}"; "wildcard conflict variable")]
+    #[test_case(r"/:id/*{id}"; "mix conflict variable")]
+    #[test_case(r"*{year}_*{month}"; "multiple wildcards in a segment")]
+    #[test_case(r"/:id(\d+"; "unclosed regex")]
+    #[test_case(r"/:id([)"; "invalid regex")]
+    #[test_case("/:id<u64"; "unclosed type")]
+    #[test_case("/:id<string>"; "unknown type")]
+    #[test_case(r"/:(\d+)"; "constraint missing variable name")]
+    fn parse_err(path: &str) {
+        assert!(path.parse::<Pattern>().is_err())
     }
 }
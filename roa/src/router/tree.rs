@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::path::{Constraint, Pattern, Segment};
+use super::{Conflict, RouterError};
+
+/// A segment-aware radix tree of routes.
+///
+/// Children are tried by priority: static segments, then params, then wildcards.
+/// Constrained params are tried in order of registration, before the unconstrained one.
+/// Lower priority children are tried only if higher ones fail to match the rest path.
+/// A wildcard matches as many segments as possible while the rest pattern still matches,
+/// and it catches all the rest path only if nothing else matches.
+/// Ends of a wildcard are pruned by the number of segments its child can match,
+/// so routing is linear to the path length unless wildcards are nested deeply.
+pub struct Node<T> {
+    route: Option<Route<T>>,
+    statics: HashMap<String, Node<T>>,
+    params: Vec<Param<T>>,
+    wildcards: Vec<Wildcard<T>>,
+    /// The minimum number of segments to match a route in this subtree.
+    min_len: usize,
+    /// The maximum number of segments to match a route in this subtree, `None` if unbounded.
+    max_len: Option<usize>,
+}
+
+/// A captured value of variable, built only when a route is matched.
+enum Capture<'p> {
+    /// A segment captured by param.
+    Segment(&'p str),
+
+    /// Segments captured by wildcard, without the length of static prefix and suffix.
+    Segments {
+        segments: &'p [&'p str],
+        prefix: usize,
+        suffix: usize,
+    },
+}
+
+impl Capture<'_> {
+    fn value(&self) -> String {
+        match self {
+            Capture::Segment(segment) => segment.to_string(),
+            Capture::Segments {
+                segments,
+                prefix,
+                suffix,
+            } => {
+                let joined = segments.join("/");
+                joined[*prefix..joined.len() - suffix].to_string()
+            }
+        }
+    }
+}
+
+/// A route registered in the tree.
+pub struct Route<T> {
+    pub pattern: Arc<str>,
+    pub vars: Vec<String>,
+    pub value: T,
+}
+
+/// A param child, the unconstrained one is the last.
+struct Param<T> {
+    constraint: Option<Constraint>,
+    node: Node<T>,
+}
+
+/// A wildcard child, sorted by specificity.
+struct Wildcard<T> {
+    prefix: String,
+    suffix: String,
+    constraint: Option<Constraint>,
+    /// The first pattern registered through this child, to report conflicts.
+    pattern: String,
+    node: Node<T>,
+}
+
+impl<T> Wildcard<T> {
+    /// The length of static parts.
+    fn specificity(&self) -> usize {
+        self.prefix.len() + self.suffix.len()
+    }
+
+    /// Whether this wildcard is at least as specific as the other one,
+    /// that is, anything matched by this wildcard is matched by the other.
+    fn covered_by(&self, prefix: &str, suffix: &str) -> bool {
+        self.prefix.starts_with(prefix) && self.suffix.ends_with(suffix)
+    }
+
+    /// Whether segments match the static prefix and suffix, without joining them.
+    /// The value must not be empty, and the constraint is not checked.
+    fn matches(&self, segments: &[&str]) -> bool {
+        match segments {
+            [] => false,
+            [segment] => {
+                segment.len() > self.specificity()
+                    && segment.starts_with(&self.prefix)
+                    && segment.ends_with(&self.suffix)
+            }
+            [first, .., last] => first.starts_with(&self.prefix) && last.ends_with(&self.suffix),
+        }
+    }
+
+    /// Whether this wildcard should be tried before the other one.
+    fn precedes(&self, other: &Self) -> bool {
+        self.specificity() > other.specificity()
+            || (self.specificity() == other.specificity()
+                && self.constraint.is_some()
+                && other.constraint.is_none())
+    }
+}
+
+/// Whether the value satisfies the optional constraint.
+fn satisfy(constraint: &Option<Constraint>, value: &str) -> bool {
+    constraint
+        .as_ref()
+        .is_none_or(|constraint| constraint.matches(value))
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            route: None,
+            statics: HashMap::new(),
+            params: Vec::new(),
+            wildcards: Vec::new(),
+            min_len: usize::MAX,
+            max_len: Some(0),
+        }
+    }
+}
+
+impl<T> Node<T> {
+    /// Insert a route, fail if it conflicts with a registered one.
+    pub fn insert(&mut self, pattern: Pattern, value: T) -> Result<(), RouterError> {
+        let mut node = &mut *self;
+        for segment in pattern.segments.iter() {
+            node = match segment {
+                Segment::Static(segment) => node.statics.entry(segment.clone()).or_default(),
+                Segment::Param { constraint, .. } => node.param_child(constraint),
+                Segment::Wildcard {
+                    prefix,
+                    suffix,
+                    constraint,
+                    ..
+                } => node.wildcard_child(prefix, suffix, constraint, &pattern.raw)?,
+            };
+        }
+        if let Some(route) = &node.route {
+            return Err(if *route.pattern == pattern.raw {
+                Conflict::Path(pattern.raw)
+            } else {
+                Conflict::Ambiguous(route.pattern.to_string(), pattern.raw)
+            }
+            .into());
+        }
+        node.route = Some(Route {
+            vars: pattern.vars().map(ToString::to_string).collect(),
+            pattern: pattern.raw.into(),
+            value,
+        });
+        self.update_len();
+        Ok(())
+    }
+
+    /// Update the bounds of segments to match routes in this subtree.
+    fn update_len(&mut self) {
+        let (mut min_len, mut max_len) = match self.route {
+            Some(_) => (0, Some(0)),
+            None => (usize::MAX, Some(0)),
+        };
+        let children = self
+            .statics
+            .values_mut()
+            .chain(self.params.iter_mut().map(|param| &mut param.node));
+        for child in children {
+            child.update_len();
+            min_len = min_len.min(child.min_len.saturating_add(1));
+            max_len = max_len
+                .zip(child.max_len)
+                .map(|(max, child_max)| max.max(child_max + 1));
+        }
+        for wildcard in self.wildcards.iter_mut() {
+            wildcard.node.update_len();
+            min_len = min_len.min(wildcard.node.min_len.saturating_add(1));
+            max_len = None;
+        }
+        self.min_len = min_len;
+        self.max_len = max_len;
+    }
+
+    /// Get or insert a param child.
+    fn param_child(&mut self, constraint: &Option<Constraint>) -> &mut Node<T> {
+        let index = match self
+            .params
+            .iter()
+            .position(|param| param.constraint == *constraint)
+        {
+            Some(index) => index,
+            None => {
+                let param = Param {
+                    constraint: constraint.clone(),
+                    node: Node::default(),
+                };
+                // the unconstrained one is the last.
+                let index = match self.params.last() {
+                    Some(last) if last.constraint.is_none() => self.params.len() - 1,
+                    _ => self.params.len(),
+                };
+                self.params.insert(index, param);
+                index
+            }
+        };
+        &mut self.params[index].node
+    }
+
+    /// Get or insert a wildcard child.
+    /// Siblings must be ordered by specificity, or they are ambiguous.
+    fn wildcard_child(
+        &mut self,
+        prefix: &str,
+        suffix: &str,
+        constraint: &Option<Constraint>,
+        pattern: &str,
+    ) -> Result<&mut Node<T>, RouterError> {
+        let mut index = None;
+        for (i, wildcard) in self.wildcards.iter().enumerate() {
+            if wildcard.prefix == prefix
+                && wildcard.suffix == suffix
+                && wildcard.constraint == *constraint
+            {
+                index = Some(i);
+                break;
+            }
+            let comparable = wildcard.covered_by(prefix, suffix)
+                || (prefix.starts_with(&wildcard.prefix) && suffix.ends_with(&wildcard.suffix));
+            if !comparable {
+                return Err(
+                    Conflict::Ambiguous(wildcard.pattern.clone(), pattern.to_string()).into(),
+                );
+            }
+        }
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let wildcard = Wildcard {
+                    prefix: prefix.to_string(),
+                    suffix: suffix.to_string(),
+                    constraint: constraint.clone(),
+                    pattern: pattern.to_string(),
+                    node: Node::default(),
+                };
+                // more specific wildcards first.
+                let index = self
+                    .wildcards
+                    .iter()
+                    .position(|other| wildcard.precedes(other))
+                    .unwrap_or(self.wildcards.len());
+                self.wildcards.insert(index, wildcard);
+                index
+            }
+        };
+        Ok(&mut self.wildcards[index].node)
+    }
+
+    /// Find the route matching segments, push values of variables in order.
+    pub fn find<'a>(&'a self, segments: &[&str], values: &mut Vec<String>) -> Option<&'a Route<T>> {
+        let mut captures = Vec::new();
+        let route = self.find_captures(segments, &mut captures)?;
+        values.extend(captures.iter().map(Capture::value));
+        Some(route)
+    }
+
+    /// Whether a number of segments is in the bounds of this subtree.
+    fn fits(&self, len: usize) -> bool {
+        self.min_len <= len && self.max_len.is_none_or(|max_len| len <= max_len)
+    }
+
+    /// Find the route matching segments, push captures of variables in order.
+    fn find_captures<'p>(
+        &self,
+        segments: &'p [&'p str],
+        captures: &mut Vec<Capture<'p>>,
+    ) -> Option<&Route<T>> {
+        if !self.fits(segments.len()) {
+            return None;
+        }
+        let (first, rest) = match segments.split_first() {
+            None => return self.route.as_ref(),
+            Some(split) => split,
+        };
+        if let Some(route) = self
+            .statics
+            .get(*first)
+            .and_then(|child| child.find_captures(rest, captures))
+        {
+            return Some(route);
+        }
+        if !first.is_empty() {
+            for param in self.params.iter() {
+                if satisfy(&param.constraint, first) {
+                    captures.push(Capture::Segment(first));
+                    if let Some(route) = param.node.find_captures(rest, captures) {
+                        return Some(route);
+                    }
+                    captures.pop();
+                }
+            }
+        }
+        for wildcard in self.wildcards.iter() {
+            // the longest match followed by the rest pattern first,
+            // catch all the rest path only if nothing else matches.
+            let ends = (1..segments.len()).rev().chain([segments.len()]);
+            for end in ends {
+                if !wildcard.node.fits(segments.len() - end) || !wildcard.matches(&segments[..end])
+                {
+                    continue;
+                }
+                let index = captures.len();
+                captures.push(Capture::Segments {
+                    segments: &segments[..end],
+                    prefix: wildcard.prefix.len(),
+                    suffix: wildcard.suffix.len(),
+                });
+                if let Some(route) = wildcard.node.find_captures(&segments[end..], captures) {
+                    // check the constraint only if the rest path matches.
+                    if wildcard.constraint.is_none()
+                        || satisfy(&wildcard.constraint, &captures[index].value())
+                    {
+                        return Some(route);
+                    }
+                }
+                captures.truncate(index);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::Node;
+    use crate::router::path::segments;
+    use crate::router::{Conflict, RouterError};
+
+    fn tree(patterns: &[&str]) -> Node<usize> {
+        let mut node = Node::default();
+        for (index, pattern) in patterns.iter().enumerate() {
+            node.insert(pattern.parse().unwrap(), index).unwrap();
+        }
+        node
+    }
+
+    fn find(node: &Node<usize>, path: &str) -> Option<(String, Vec<String>)> {
+        let mut values = Vec::new();
+        node.find(&segments(path), &mut values)
+            .map(|route| (route.pattern.to_string(), values))
+    }
+
+    fn matched(pattern: &str, values: &[&str]) -> Option<(String, Vec<String>)> {
+        Some((
+            pattern.to_string(),
+            values.iter().map(ToString::to_string).collect(),
+        ))
+    }
+
+    #[test_case("/user/1/" => matched("/user/:id", &["1"]))]
+    #[test_case("/user/65535" => matched("/user/:id", &["65535"]))]
+    #[test_case("/user/new" => matched("/user/new", &[]))]
+    #[test_case("/user/new/posts" => matched("/user/:id/posts", &["new"]); "backtrack to param")]
+    #[test_case("/user/1/posts/2020/01" => matched("/user/:id/posts/*{date}", &["1", "2020/01"]))]
+    #[test_case("/2000/01/01" => matched("/:year/:month/:day", &["2000", "01", "01"]))]
+    #[test_case("/usr/include/boost/boost.h" => matched("/usr/include/*{dir}/*{file}.h", &["boost", "boost"]))]
+    #[test_case("/usr/include/uv/uv.h" => matched("/usr/include/*{dir}/*{file}.h", &["uv", "uv"]))]
+    #[test_case("/usr/include/a/b/c.h" => matched("/usr/include/*{dir}/*{file}.h", &["a/b", "c"]))]
+    #[test_case("/usr/include/a/b/c" => matched("/usr/include/*{path}", &["a/b/c"]))]
+    #[test_case("/srv/static/app/index.html" => matched("/srv/static/*{path}", &["app/index.html"]))]
+    #[test_case("/srv/static/../../index.html" => matched("/srv/static/*{path}", &["../../index.html"]))]
+    #[test_case("/" => matched("/", &[]))]
+    #[test_case("/user" => None)]
+    #[test_case("/srv/static" => None; "wildcard matches at least one segment")]
+    #[test_case("/2000/01" => None)]
+    fn lookup(path: &str) -> Option<(String, Vec<String>)> {
+        let node = tree(&[
+            "/",
+            "/user/:id",
+            "/user/new",
+            "/user/:id/posts",
+            "/user/:id/posts/*{date}",
+            "/:year/:month/:day",
+            "/usr/include/*{path}",
+            "/usr/include/*{dir}/*{file}.h",
+            "/srv/static/*{path}",
+        ]);
+        find(&node, path)
+    }
+
+    #[test]
+    fn priority_is_independent_of_order() {
+        let patterns = ["/files/*{path}", "/files/:name", "/files/index"];
+        for reversed in [false, true] {
+            let mut patterns = patterns.to_vec();
+            if reversed {
+                patterns.reverse();
+            }
+            let node = tree(&patterns);
+            assert_eq!(matched("/files/index", &[]), find(&node, "/files/index"));
+            assert_eq!(matched("/files/:name", &["a"]), find(&node, "/files/a"));
+            assert_eq!(
+                matched("/files/*{path}", &["a/b"]),
+                find(&node, "/files/a/b")
+            );
+        }
+    }
+
+    #[test]
+    fn long_path() {
+        let node = tree(&["/usr/include/*{dir}/*{file}.h", "/usr/*{a}/x/*{b}/y/*{c}.h"]);
+        let dir = vec!["a"; 2000].join("/");
+        assert_eq!(None, find(&node, &format!("/usr/include/{}/x", dir)));
+        assert_eq!(None, find(&node, &format!("/usr/{}/x/{}/y/z", dir, dir)));
+        assert_eq!(
+            matched("/usr/include/*{dir}/*{file}.h", &[&dir, "uv"]),
+            find(&node, &format!("/usr/include/{}/uv.h", dir))
+        );
+    }
+
+    #[test_case("/users/1" => matched(r"/users/:id(\d+)", &["1"]))]
+    #[test_case("/users/hexilee" => matched("/users/:name", &["hexilee"]))]
+    #[test_case("/users/1/posts" => matched("/users/:name/posts", &["1"]); "backtrack to unconstrained")]
+    #[test_case("/files/app.js" => matched(r"/files/*{path(.+\.js)}", &["app.js"]))]
+    #[test_case("/files/js/app.js" => matched(r"/files/*{path(.+\.js)}", &["js/app.js"]))]
+    #[test_case("/files/app.css" => matched("/files/*{path}", &["app.css"]))]
+    #[test_case("/posts/1" => matched("/posts/:id<u64>", &["1"]))]
+    #[test_case("/posts/x" => None)]
+    fn lookup_constraint(path: &str) -> Option<(String, Vec<String>)> {
+        let node = tree(&[
+            "/users/:name",
+            r"/users/:id(\d+)",
+            "/users/:name/posts",
+            "/files/*{path}",
+            r"/files/*{path(.+\.js)}",
+            "/posts/:id<u64>",
+        ]);
+        find(&node, path)
+    }
+
+    #[test]
+    fn overlapping_constraints_in_registration_order() {
+        let patterns = [r"/u/:id(\d+)", "/u/:n<u64>"];
+        for reversed in [false, true] {
+            let mut patterns = patterns.to_vec();
+            if reversed {
+                patterns.reverse();
+            }
+            let node = tree(&patterns);
+            assert_eq!(matched(patterns[0], &["1"]), find(&node, "/u/1"));
+            // only `\d+` matches integers out of range of u64
+            let large = "1".repeat(30);
+            assert_eq!(
+                matched(r"/u/:id(\d+)", &[&large]),
+                find(&node, &format!("/u/{}", large))
+            );
+        }
+    }
+
+    #[test_case(&["/user/:id", "/user/:id/"] => Conflict::Path("/user/:id".to_string()); "same path")]
+    #[test_case(&["/user/:id", "/user/:name"] => Conflict::Ambiguous("/user/:id".to_string(), "/user/:name".to_string()); "different variables")]
+    #[test_case(&["/user/:id<u64>", "/user/:name<u64>"] => Conflict::Ambiguous("/user/:id<u64>".to_string(), "/user/:name<u64>".to_string()); "same constraint")]
+    #[test_case(&["/*{a}-x", "/x-*{b}"] => Conflict::Ambiguous("/*{a}-x".to_string(), "/x-*{b}".to_string()); "incomparable wildcards")]
+    fn conflict(patterns: &[&str]) -> Conflict {
+        let mut node = Node::default();
+        node.insert(patterns[0].parse().unwrap(), 0).unwrap();
+        match node.insert(patterns[1].parse().unwrap(), 1) {
+            Err(RouterError::Conflict(conflict)) => conflict,
+            _ => panic!("`{}` should conflict with `{}`", patterns[1], patterns[0]),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use super::path::{Constraint, Pattern, Segment};
+use super::{Conflict, RouterError};
+
+/// Characters to be percent-encoded in a path segment, all but unreserved ones.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// A builder of URLs from named routes, stored as a context extension by `RouteTable`.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa::router::{get, Router, Urls};
+/// use roa::{App, Context, Status};
+///
+/// async fn user(ctx: &mut Context) -> Result<(), Status> {
+///     Ok(())
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let router = Router::new().named("user", "/:id", get(user));
+/// let route_table = router.routes("/api/user")?;
+/// let urls: &Urls = route_table.urls();
+/// assert_eq!("/api/user/1", urls.url_for("user", [("id", "1")])?);
+/// assert_eq!("/api/user/a%20b", urls.url_for("user", [("id", "a b")])?);
+/// assert!(urls.url_for("post", [("id", "1")]).is_err());
+/// assert!(urls.url_for("user", [("name", "1")]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Urls(Arc<HashMap<String, Pattern>>);
+
+impl Urls {
+    /// Register a named route, fail if the name is already used.
+    pub(crate) fn insert(&mut self, name: String, pattern: Pattern) -> Result<(), RouterError> {
+        let routes = Arc::make_mut(&mut self.0);
+        if routes.contains_key(&name) {
+            return Err(Conflict::Name(name).into());
+        }
+        routes.insert(name, pattern);
+        Ok(())
+    }
+
+    /// Build the path of a named route, filling in variables with percent-encoded params.
+    ///
+    /// Slashes in values of wildcards are kept, params not in the route are ignored.
+    /// Fail if the name is unknown, a variable is missing,
+    /// or a value doesn't satisfy the constraint of its variable.
+    pub fn url_for<K, V>(
+        &self,
+        name: &str,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<String, RouterError>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let pattern = self
+            .0
+            .get(name)
+            .ok_or_else(|| RouterError::UnknownRoute(name.to_string()))?;
+        let params = params.into_iter().collect::<Vec<_>>();
+        // get the value of a variable, and check it.
+        let param = |var: &str, constraint: &Option<Constraint>| {
+            let value = params
+                .iter()
+                .find(|(key, _)| key.as_ref() == var)
+                .map(|(_, value)| value.as_ref())
+                .ok_or_else(|| RouterError::MissingParam {
+                    route: name.to_string(),
+                    var: var.to_string(),
+                })?;
+            if value.is_empty() || !constraint.as_ref().is_none_or(|c| c.matches(value)) {
+                return Err(RouterError::InvalidParam {
+                    route: name.to_string(),
+                    var: var.to_string(),
+                    value: value.to_string(),
+                });
+            }
+            Ok(value)
+        };
+
+        let mut url = String::new();
+        for segment in pattern.segments.iter() {
+            url.push('/');
+            match segment {
+                Segment::Static(segment) => url.extend(utf8_percent_encode(segment, SEGMENT)),
+                Segment::Param { name, constraint } => {
+                    url.extend(utf8_percent_encode(param(name, constraint)?, SEGMENT));
+                }
+                Segment::Wildcard {
+                    prefix,
+                    name,
+                    suffix,
+                    constraint,
+                } => {
+                    url.extend(utf8_percent_encode(prefix, SEGMENT));
+                    for (index, part) in param(name, constraint)?.split('/').enumerate() {
+                        if index > 0 {
+                            url.push('/');
+                        }
+                        url.extend(utf8_percent_encode(part, SEGMENT));
+                    }
+                    url.extend(utf8_percent_encode(suffix, SEGMENT));
+                }
+            }
+        }
+        if url.is_empty() {
+            url.push('/');
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::Urls;
+    use crate::router::RouterError;
+
+    fn urls() -> Urls {
+        let mut urls = Urls::default();
+        for (name, pattern) in [
+            ("root", "/"),
+            ("user", "/user/:id"),
+            ("post", "/user/:id<u64>/posts/:title"),
+            ("file", "/static/*{path}"),
+            ("header", "/include/*{file}.h"),
+            ("space", "/hello world/:id"),
+        ] {
+            urls.insert(name.to_string(), pattern.parse().unwrap())
+                .unwrap();
+        }
+        urls
+    }
+
+    #[test_case("root", &[] => "/")]
+    #[test_case("user", &[("id", "1")] => "/user/1")]
+    #[test_case("user", &[("id", "1"), ("page", "2")] => "/user/1"; "extra params")]
+    #[test_case("user", &[("id", "a/b?c")] => "/user/a%2Fb%3Fc"; "percent encoding")]
+    #[test_case("post", &[("title", "中文"), ("id", "1")] => "/user/1/posts/%E4%B8%AD%E6%96%87")]
+    #[test_case("file", &[("path", "css/a b.css")] => "/static/css/a%20b.css"; "wildcard keeps slashes")]
+    #[test_case("header", &[("file", "uv/uv")] => "/include/uv/uv.h")]
+    #[test_case("space", &[("id", "1")] => "/hello%20world/1"; "encode static")]
+    fn url_for(name: &str, params: &[(&str, &str)]) -> String {
+        urls().url_for(name, params.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn url_for_err() {
+        let urls = urls();
+        assert!(matches!(
+            urls.url_for("users", [("id", "1")]),
+            Err(RouterError::UnknownRoute(name)) if name == "users"
+        ));
+        assert!(matches!(
+            urls.url_for("post", [("id", "1")]),
+            Err(RouterError::MissingParam { var, .. }) if var == "title"
+        ));
+        assert!(matches!(
+            urls.url_for("post", [("id", "x"), ("title", "a")]),
+            Err(RouterError::InvalidParam { var, .. }) if var == "id"
+        ));
+        assert!(matches!(
+            urls.url_for("user", [("id", "")]),
+            Err(RouterError::InvalidParam { var, .. }) if var == "id"
+        ));
+    }
+}
@@ -38,11 +38,38 @@
 //! Ok(())
 //! # }
 //! ```
+//!
+//! ### ThreadPerCore
+//!
+//! ```no_run
+//! use roa::{App, Context, Result};
+//! use roa::tcp::ThreadPerCore;
+//! use std::cell::RefCell;
+//! use std::io;
+//! use std::rc::Rc;
+//!
+//! async fn end(ctx: &mut Context<Rc<RefCell<Vec<u8>>>>) -> Result {
+//!     ctx.borrow_mut().push(0);
+//!     Ok(())
+//! }
+//!
+//! fn main() -> io::Result<()> {
+//!     let (addr, workers) = ThreadPerCore::new(|| App::state(Rc::default()).end(end))
+//!         .bind("127.0.0.1:0")?;
+//!     workers.join()
+//! }
+//! ```
 
 mod incoming;
 mod listener;
+#[cfg(feature = "runtime")]
+mod thread_per_core;
 
 #[doc(inline)]
 pub use incoming::TcpIncoming;
 #[doc(inline)]
 pub use listener::Listener;
+#[cfg(feature = "runtime")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "runtime")))]
+#[doc(inline)]
+pub use thread_per_core::{ThreadPerCore, Workers};
@@ -7,7 +7,7 @@ use std::task::{self, Poll};
 use std::time::Duration;
 use std::{fmt, io, matches};
 
-use roa_core::{Accept, AddrStream};
+use roa_core::{Accept, AddrStream, ConnInfo};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::time::{sleep, Sleep};
 use tracing::{debug, error, trace};
@@ -148,7 +148,11 @@ impl Accept for TcpIncoming {
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
         let (stream, addr) = futures::ready!(self.poll_stream(cx))?;
-        Poll::Ready(Some(Ok(AddrStream::new(addr, stream))))
+        let mut info = ConnInfo::new();
+        if let Ok(local_addr) = stream.local_addr() {
+            info = info.with_local_addr(local_addr);
+        }
+        Poll::Ready(Some(Ok(AddrStream::new(addr, stream).with_info(info))))
     }
 }
 
@@ -1,7 +1,9 @@
+use std::future::Future;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::Arc;
+use std::time::Duration;
 
-use roa_core::{App, Endpoint, Executor, Server, State};
+use roa_core::{App, Endpoint, Executor, Graceful, Server, State};
 
 use super::TcpIncoming;
 
@@ -43,18 +45,29 @@ pub trait Listener {
     /// }
     /// ```
     fn run(self) -> std::io::Result<(SocketAddr, Self::Server)>;
+
+    /// Listen on a socket addr, return a graceful server and the real addr it binds.
+    ///
+    /// The server stops accepting new connections once `signal` resolves,
+    /// then waits up to `drain_timeout` for in-flight requests and spawned tasks.
+    fn bind_graceful(
+        self,
+        addr: impl ToSocketAddrs,
+        signal: impl 'static + Send + Future<Output = ()>,
+        drain_timeout: Duration,
+    ) -> std::io::Result<(SocketAddr, Graceful)>;
 }
 
 impl<S, E> Listener for App<S, Arc<E>>
 where
-    S: State,
-    E: for<'a> Endpoint<'a, S>,
+    S: State + Send + Sync,
+    E: Send + Sync + for<'a> Endpoint<'a, S>,
 {
     type Server = Server<TcpIncoming, Self, Executor>;
     fn bind(self, addr: impl ToSocketAddrs) -> std::io::Result<(SocketAddr, Self::Server)> {
         let incoming = TcpIncoming::bind(addr)?;
         let local_addr = incoming.local_addr();
-        Ok((local_addr, self.accept(incoming)))
+        Ok((local_addr, self.try_accept(incoming)?))
     }
 
     fn listen(
@@ -70,4 +83,274 @@ where
     fn run(self) -> std::io::Result<(SocketAddr, Self::Server)> {
         self.bind("127.0.0.1:0")
     }
+
+    fn bind_graceful(
+        self,
+        addr: impl ToSocketAddrs,
+        signal: impl 'static + Send + Future<Output = ()>,
+        drain_timeout: Duration,
+    ) -> std::io::Result<(SocketAddr, Graceful)> {
+        let incoming = TcpIncoming::bind(addr)?;
+        let local_addr = incoming.local_addr();
+        Ok((
+            local_addr,
+            self.accept_graceful(incoming, signal, drain_timeout),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::time::{Duration, Instant};
+
+    use futures::channel::oneshot;
+    use hyper::body::HttpBody;
+    use tokio::time::sleep;
+
+    use super::Listener;
+    use crate::http::{HeaderMap, HeaderValue, StatusCode, Version};
+    use crate::{App, Context, HttpConfig};
+
+    async fn slow(ctx: &mut Context) -> crate::Result {
+        sleep(Duration::from_millis(500)).await;
+        ctx.resp.write("done");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drain_in_flight_requests() -> Result<(), Box<dyn Error>> {
+        let (trigger, signal) = oneshot::channel::<()>();
+        let (addr, server) = App::new().end(slow).bind_graceful(
+            "127.0.0.1:0",
+            async move {
+                let _ = signal.await;
+            },
+            Duration::from_secs(5),
+        )?;
+        let server = tokio::spawn(server);
+        let client = reqwest::Client::new();
+        let request = tokio::spawn(client.get(format!("http://{}", addr)).send());
+        sleep(Duration::from_millis(100)).await;
+        trigger.send(()).unwrap();
+        let resp = request.await??;
+        assert_eq!(StatusCode::OK, resp.status());
+        assert_eq!("done", resp.text().await?);
+        server.await??;
+        assert!(reqwest::get(format!("http://{}", addr)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drain_timeout() -> Result<(), Box<dyn Error>> {
+        let (trigger, signal) = oneshot::channel::<()>();
+        async fn end(ctx: &mut Context) -> crate::Result {
+            // a long-running task, like a websocket connection
+            ctx.exec.spawn(sleep(Duration::from_secs(10)));
+            Ok(())
+        }
+        let (addr, server) = App::new().end(end).bind_graceful(
+            "127.0.0.1:0",
+            async move {
+                let _ = signal.await;
+            },
+            Duration::from_millis(100),
+        )?;
+        let server = tokio::spawn(server);
+        let resp = reqwest::get(format!("http://{}", addr)).await?;
+        assert_eq!(StatusCode::OK, resp.status());
+        let start = Instant::now();
+        trigger.send(()).unwrap();
+        server.await??;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn h2c_prior_knowledge() -> Result<(), Box<dyn Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            let version = format!("{:?}", ctx.version());
+            ctx.resp.write(version);
+            Ok(())
+        }
+        let config = HttpConfig::new()
+            .http2_only(true)
+            .http2_max_concurrent_streams(16);
+        let (addr, server) = App::new().http_config(config).end(end).run()?;
+        tokio::spawn(server);
+        let client = reqwest::Client::builder().http2_prior_knowledge().build()?;
+        let resp = client.get(format!("http://{}", addr)).send().await?;
+        assert_eq!(StatusCode::OK, resp.status());
+        assert_eq!(Version::HTTP_2, resp.version());
+        assert_eq!("HTTP/2.0", resp.text().await?);
+
+        // HTTP/1.1 is not served
+        assert!(reqwest::get(format!("http://{}", addr)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn h2c_trailers() -> Result<(), Box<dyn Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", HeaderValue::from_static("0"));
+            ctx.resp.write("Hello, World").set_trailers(trailers);
+            Ok(())
+        }
+        let config = HttpConfig::new().http2_only(true);
+        let (addr, server) = App::new().http_config(config).end(end).run()?;
+        tokio::spawn(server);
+        let client = hyper::Client::builder()
+            .http2_only(true)
+            .build_http::<hyper::Body>();
+        let mut resp = client.get(format!("http://{}", addr).parse()?).await?;
+        assert_eq!(StatusCode::OK, resp.status());
+        let body = hyper::body::to_bytes(resp.body_mut()).await?;
+        assert_eq!(b"Hello, World", body.as_ref());
+        let trailers = resp.body_mut().trailers().await?.expect("no trailers");
+        assert_eq!("0", trailers["grpc-status"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conn_info() -> Result<(), Box<dyn Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            assert!(!ctx.conn.is_tls());
+            let local_addr = ctx.conn.local_addr().expect("no local addr");
+            ctx.resp.write(local_addr.to_string());
+            Ok(())
+        }
+        let (addr, server) = App::new().end(end).run()?;
+        tokio::spawn(server);
+        let resp = reqwest::get(format!("http://{}", addr)).await?;
+        assert_eq!(StatusCode::OK, resp.status());
+        assert_eq!(addr.to_string(), resp.text().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancel_on_hang_up() -> Result<(), Box<dyn Error>> {
+        use futures::channel::mpsc::{unbounded, UnboundedSender};
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpStream;
+        use tokio::time::timeout;
+
+        use crate::CancellationToken;
+
+        async fn end(ctx: &mut Context<UnboundedSender<CancellationToken>>) -> crate::Result {
+            ctx.unbounded_send(ctx.cancellation_token().clone())?;
+            futures::future::pending().await
+        }
+
+        let (sender, mut receiver) = unbounded();
+        let (addr, server) = App::state(sender).end(end).run()?;
+        tokio::spawn(server);
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await?;
+        let token = receiver.next().await.expect("request is not handled");
+        assert!(!token.is_cancelled());
+        drop(stream);
+        timeout(Duration::from_secs(5), token.cancelled()).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lifecycle() -> Result<(), Box<dyn Error>> {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{Executor, Shutdown};
+
+        #[derive(Clone, Default)]
+        struct Events(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Events {
+            fn push(&self, event: &'static str) {
+                self.0.lock().unwrap().push(event)
+            }
+        }
+
+        async fn start(events: Events, _exec: Executor) -> crate::Result {
+            events.push("start");
+            Ok(())
+        }
+
+        async fn task(events: Events, shutdown: Shutdown) -> crate::Result {
+            shutdown.wait().await;
+            sleep(Duration::from_millis(100)).await;
+            events.push("task done");
+            Ok(())
+        }
+
+        async fn stop(events: Events, _exec: Executor) -> crate::Result {
+            events.push("shutdown");
+            Ok(())
+        }
+
+        async fn end(ctx: &mut Context<Events>) -> crate::Result {
+            ctx.push("request");
+            Ok(())
+        }
+
+        let events = Events::default();
+        let (trigger, signal) = oneshot::channel::<()>();
+        let (addr, server) = App::state(events.clone())
+            .on_start(start)
+            .background(task)
+            .on_shutdown(stop)
+            .end(end)
+            .bind_graceful(
+                "127.0.0.1:0",
+                async move {
+                    let _ = signal.await;
+                },
+                Duration::from_secs(5),
+            )?;
+        let server = tokio::spawn(server);
+        let resp = reqwest::get(format!("http://{}", addr)).await?;
+        assert_eq!(StatusCode::OK, resp.status());
+        trigger.send(()).unwrap();
+        server.await??;
+        assert_eq!(
+            vec!["start", "request", "task done", "shutdown"],
+            *events.0.lock().unwrap()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fail_to_start() -> Result<(), Box<dyn Error>> {
+        async fn start(_state: (), _exec: crate::Executor) -> crate::Result {
+            crate::throw!(StatusCode::INTERNAL_SERVER_ERROR, "no database")
+        }
+        let (_, server) = App::new().on_start(start).end(()).bind_graceful(
+            "127.0.0.1:0",
+            futures::future::pending(),
+            Duration::from_secs(5),
+        )?;
+        let err = server.await.unwrap_err();
+        assert!(err.to_string().contains("no database"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_unmanaged_hooks() -> Result<(), Box<dyn Error>> {
+        async fn start(_state: (), _exec: crate::Executor) -> crate::Result {
+            Ok(())
+        }
+        assert!(App::new().end(()).run().is_ok());
+        let err = match App::new().on_start(start).end(()).run() {
+            Ok(_) => panic!("hooks are ignored"),
+            Err(err) => err,
+        };
+        assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+        assert!(
+            err.to_string().contains("need graceful shutdown"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
 }
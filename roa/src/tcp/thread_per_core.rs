@@ -0,0 +1,264 @@
+use std::io;
+use std::net::{SocketAddr, TcpListener as StdListener, ToSocketAddrs};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use roa_core::{App, Endpoint, State};
+#[cfg(unix)]
+use socket2::{Domain, Socket, Type};
+use tokio::runtime::Builder;
+use tokio::task::LocalSet;
+
+use super::TcpIncoming;
+
+/// A server running one current-thread runtime per core.
+///
+/// Each worker builds its own app by the factory, and serves connections
+/// of its own listener on a `LocalSet`. The listeners are bound to the same address
+/// with `SO_REUSEPORT` on unix, so connections are balanced between workers by kernel.
+///
+/// Apps are never moved between threads, so the state, middlewares and their futures
+/// can be `!Send`, like `Rc`, `RefCell` or thread-local caches.
+/// `Executor::spawn_local` is supported in workers.
+///
+/// ### Example
+/// ```rust,no_run
+/// use roa::tcp::ThreadPerCore;
+/// use roa::{App, Context, Result};
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// async fn end(ctx: &mut Context<Rc<RefCell<u64>>>) -> Result {
+///     // requests served by this worker
+///     *ctx.borrow_mut() += 1;
+///     let count = *ctx.borrow();
+///     ctx.resp.write(count.to_string());
+///     Ok(())
+/// }
+///
+/// fn main() -> std::io::Result<()> {
+///     let (addr, workers) = ThreadPerCore::new(|| App::state(Rc::default()).end(end))
+///         .bind("127.0.0.1:8000")?;
+///     println!("server is listening on {}", addr);
+///     workers.join()
+/// }
+/// ```
+pub struct ThreadPerCore<F> {
+    factory: Arc<F>,
+    workers: usize,
+}
+
+/// Worker threads of a server, returned by `ThreadPerCore::bind`.
+pub struct Workers(Vec<JoinHandle<io::Result<()>>>);
+
+impl<F, S, E> ThreadPerCore<F>
+where
+    F: 'static + Send + Sync + Fn() -> App<S, Arc<E>>,
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    /// Construct a server by an app factory, with one worker per available core.
+    pub fn new(factory: F) -> Self {
+        let workers = thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+        Self {
+            factory: Arc::new(factory),
+            workers,
+        }
+    }
+
+    /// Set the number of workers.
+    ///
+    /// ### Panics
+    /// Panics if `workers` is zero.
+    pub fn workers(mut self, workers: usize) -> Self {
+        assert!(workers > 0, "the number of workers must be positive");
+        self.workers = workers;
+        self
+    }
+
+    /// Bind all workers to an address, then start serving.
+    pub fn bind(self, addr: impl ToSocketAddrs) -> io::Result<(SocketAddr, Workers)> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to bind"))?;
+        let listeners = bind_listeners(addr, self.workers)?;
+        let local_addr = listeners[0].local_addr()?;
+        let mut handles = Vec::with_capacity(listeners.len());
+        for (index, listener) in listeners.into_iter().enumerate() {
+            let factory = self.factory.clone();
+            let handle = thread::Builder::new()
+                .name(format!("roa-worker-{}", index))
+                .spawn(move || serve(listener, &*factory))?;
+            handles.push(handle);
+        }
+        Ok((local_addr, Workers(handles)))
+    }
+
+    /// Bind all workers to a random port of 127.0.0.1, then start serving.
+    /// It's useful for test.
+    pub fn run(self) -> io::Result<(SocketAddr, Workers)> {
+        self.bind("127.0.0.1:0")
+    }
+}
+
+impl Workers {
+    /// Block the current thread until all workers exit.
+    /// Return the first error of workers.
+    pub fn join(self) -> io::Result<()> {
+        let mut result = Ok(());
+        for handle in self.0 {
+            let ret = handle
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("worker panicked")));
+            if result.is_ok() {
+                result = ret;
+            }
+        }
+        result
+    }
+}
+
+/// Serve a listener on a current-thread runtime.
+fn serve<F, S, E>(listener: StdListener, factory: &F) -> io::Result<()>
+where
+    F: Fn() -> App<S, Arc<E>>,
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    LocalSet::new().block_on(&runtime, async move {
+        let incoming = TcpIncoming::from_std(listener)?;
+        factory().serve_local(incoming).await
+    })
+}
+
+/// Bind listeners sharing the same address by `SO_REUSEPORT`.
+#[cfg(unix)]
+fn bind_listeners(addr: SocketAddr, workers: usize) -> io::Result<Vec<StdListener>> {
+    let first = reuse_port_listener(addr)?;
+    // the port is decided by the first listener if it's zero.
+    let addr = first.local_addr()?;
+    let mut listeners = vec![first];
+    for _ in 1..workers {
+        listeners.push(reuse_port_listener(addr)?);
+    }
+    Ok(listeners)
+}
+
+/// Clone the listener for each worker if `SO_REUSEPORT` is not available.
+#[cfg(not(unix))]
+fn bind_listeners(addr: SocketAddr, workers: usize) -> io::Result<Vec<StdListener>> {
+    let mut listeners = vec![StdListener::bind(addr)?];
+    for _ in 1..workers {
+        listeners.push(listeners[0].try_clone()?);
+    }
+    Ok(listeners)
+}
+
+#[cfg(unix)]
+fn reuse_port_listener(addr: SocketAddr) -> io::Result<StdListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    socket.set_reuse_port(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashSet;
+    use std::error::Error;
+    use std::rc::Rc;
+    use std::thread;
+
+    use super::ThreadPerCore;
+    use crate::http::StatusCode;
+    use crate::{App, Context, Middleware, Next, Result};
+
+    /// A middleware holding a thread-local cache.
+    struct Cache(Rc<RefCell<Vec<String>>>);
+
+    impl<'a> Middleware<'a, Rc<Cell<u64>>> for Cache {
+        async fn handle(&'a self, ctx: &'a mut Context<Rc<Cell<u64>>>, next: Next<'a>) -> Result {
+            self.0.borrow_mut().push(ctx.uri().to_string());
+            next.await
+        }
+    }
+
+    /// A middleware counting requests of this worker.
+    struct Visits(Rc<Cell<u64>>);
+
+    impl<'a> Middleware<'a, Rc<Cell<u64>>> for Visits {
+        async fn handle(&'a self, _ctx: &'a mut Context<Rc<Cell<u64>>>, next: Next<'a>) -> Result {
+            self.0.set(self.0.get() + 1);
+            next.await
+        }
+    }
+
+    async fn end(ctx: &mut Context<Rc<Cell<u64>>>) -> Result {
+        let counter = Rc::clone(ctx);
+        ctx.exec
+            .spawn_local(async move { counter.set(counter.get() + 1) })
+            .await?;
+        let name = thread::current().name().unwrap_or_default().to_string();
+        ctx.resp.write(name);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn thread_per_core() -> std::result::Result<(), Box<dyn Error>> {
+        let (addr, _workers) = ThreadPerCore::new(|| {
+            App::state(Rc::new(Cell::new(0)))
+                .gate(Cache(Default::default()))
+                .end(end)
+        })
+        .workers(2)
+        .run()?;
+        let mut names = HashSet::new();
+        for _ in 0..8 {
+            // a new connection for each request
+            let client = reqwest::Client::builder()
+                .pool_max_idle_per_host(0)
+                .build()?;
+            let resp = client.get(format!("http://{}", addr)).send().await?;
+            assert_eq!(StatusCode::OK, resp.status());
+            names.insert(resp.text().await?);
+        }
+        assert!(names.iter().all(|name| name.starts_with("roa-worker-")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn local_router() -> std::result::Result<(), Box<dyn Error>> {
+        use crate::router::{get, Router};
+
+        let (addr, _workers) = ThreadPerCore::new(|| {
+            let visits = Rc::new(Cell::new(0u64));
+            let count = Rc::clone(&visits);
+            let dispatcher = get(move |ctx: &mut Context<Rc<Cell<u64>>>| {
+                ctx.resp.write(count.get().to_string());
+                async { Ok(()) }
+            });
+            let router = Router::local()
+                .gate(Cache(Default::default()))
+                .gate(Visits(visits))
+                .on("/visits", dispatcher);
+            App::state(Rc::new(Cell::new(0))).end(router.routes("/").unwrap())
+        })
+        .workers(1)
+        .run()?;
+        let client = reqwest::Client::new();
+        for visits in 1..=3 {
+            let resp = client.get(format!("http://{}/visits", addr)).send().await?;
+            assert_eq!(StatusCode::OK, resp.status());
+            assert_eq!(visits.to_string(), resp.text().await?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,514 @@
+//! This module provides an in-process test client `TestClient`,
+//! which drives an app without binding any port.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::{App, Context};
+//! use roa::http::StatusCode;
+//! use roa::preload::*;
+//! use roa::test::TestClient;
+//!
+//! async fn end(ctx: &mut Context) -> roa::Result {
+//!     let name = ctx.must_query("name")?.to_string();
+//!     ctx.resp.headers.insert("x-name", name.parse()?);
+//!     ctx.resp.write(format!("Hello, {}!", name));
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let app = App::new().gate(roa::query::query_parser).end(end);
+//!     let client = TestClient::new(app);
+//!     let resp = client.get("/?name=Hexilee").send().await;
+//!     resp.assert_status(StatusCode::OK)
+//!         .assert_header("x-name", "Hexilee");
+//!     assert_eq!("Hello, Hexilee!", resp.text().await?);
+//!     Ok(())
+//! }
+//! ```
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use futures::TryStreamExt;
+use headers::{ContentType, HeaderMapExt};
+
+use crate::http::header::{AsHeaderName, IntoHeaderName, CONTENT_TYPE, COOKIE};
+use crate::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri, Version};
+use crate::{App, Body, ConnInfo, Endpoint, HttpService, Request, Response, State};
+
+/// An in-process client to test an app.
+pub struct TestClient<S, E> {
+    service: HttpService<S, E>,
+}
+
+/// A request builder of `TestClient`.
+pub struct RequestBuilder<'c, S, E> {
+    client: &'c TestClient<S, E>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    cookies: Vec<String>,
+    remote_addr: Option<SocketAddr>,
+    conn: Option<ConnInfo>,
+    body: hyper::Body,
+}
+
+/// A response returned by `TestClient`.
+pub struct TestResponse {
+    /// Status code.
+    pub status: StatusCode,
+
+    /// Version of HTTP protocol.
+    pub version: Version,
+
+    /// Raw header map.
+    pub headers: HeaderMap,
+
+    /// Response body.
+    pub body: Body,
+}
+
+/// A multipart/form-data body.
+///
+/// ### Example
+///
+/// ```rust
+/// use roa::test::Multipart;
+///
+/// let form = Multipart::new()
+///     .text("name", "Hexilee")
+///     .file("avatar", "avatar.png", "image/png", b"...".as_ref());
+/// ```
+pub struct Multipart {
+    boundary: String,
+    data: BytesMut,
+}
+
+impl<S, E> TestClient<S, E>
+where
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    /// Construct a test client by an app.
+    pub fn new(app: App<S, Arc<E>>) -> Self {
+        Self {
+            service: app.http_service(),
+        }
+    }
+
+    /// Build a request with method and uri.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the uri is invalid.
+    pub fn request(&self, method: Method, uri: &str) -> RequestBuilder<'_, S, E> {
+        RequestBuilder {
+            client: self,
+            method,
+            uri: uri.parse().expect("invalid uri"),
+            headers: HeaderMap::new(),
+            cookies: Vec::new(),
+            remote_addr: None,
+            conn: None,
+            body: hyper::Body::empty(),
+        }
+    }
+
+    /// Build a GET request.
+    pub fn get(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::GET, uri)
+    }
+
+    /// Build a POST request.
+    pub fn post(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::POST, uri)
+    }
+
+    /// Build a PUT request.
+    pub fn put(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::PUT, uri)
+    }
+
+    /// Build a PATCH request.
+    pub fn patch(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::PATCH, uri)
+    }
+
+    /// Build a DELETE request.
+    pub fn delete(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::DELETE, uri)
+    }
+
+    /// Build a HEAD request.
+    pub fn head(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::HEAD, uri)
+    }
+
+    /// Build an OPTIONS request.
+    pub fn options(&self, uri: &str) -> RequestBuilder<'_, S, E> {
+        self.request(Method::OPTIONS, uri)
+    }
+}
+
+impl<'c, S, E> RequestBuilder<'c, S, E>
+where
+    S: State,
+    E: for<'a> Endpoint<'a, S>,
+{
+    /// Append a header.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the value is not a valid header value.
+    pub fn header(mut self, name: impl IntoHeaderName, value: &str) -> Self {
+        let value = HeaderValue::from_str(value).expect("invalid header value");
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Add a cookie.
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.cookies.push(format!("{}={}", name, value));
+        self
+    }
+
+    /// Set the remote address of client, 127.0.0.1:0 by default.
+    pub fn remote_addr(mut self, addr: SocketAddr) -> Self {
+        self.remote_addr = Some(addr);
+        self
+    }
+
+    /// Set the metadata of connection, like local address and TLS info.
+    pub fn conn_info(mut self, info: ConnInfo) -> Self {
+        self.conn = Some(info);
+        self
+    }
+
+    /// Set body with content type.
+    pub fn body(mut self, content_type: &str, body: impl Into<Bytes>) -> Self {
+        self = self.header(CONTENT_TYPE, content_type);
+        self.body = hyper::Body::from(body.into());
+        self
+    }
+
+    /// Set a plain text body.
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.body("text/plain; charset=utf-8", text.into())
+    }
+
+    /// Set a json body.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the data fails to be serialized.
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+    pub fn json(self, data: &impl serde::Serialize) -> Self {
+        let body = serde_json::to_vec(data).expect("fail to serialize json");
+        self.body("application/json", body)
+    }
+
+    /// Set a x-www-form-urlencoded body.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the data fails to be serialized.
+    #[cfg(feature = "urlencoded")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "urlencoded")))]
+    pub fn form(self, data: &impl serde::Serialize) -> Self {
+        let body = serde_urlencoded::to_string(data).expect("fail to serialize form");
+        self.body("application/x-www-form-urlencoded", body)
+    }
+
+    /// Set a multipart/form-data body.
+    pub fn multipart(self, form: Multipart) -> Self {
+        let content_type = format!("multipart/form-data; boundary={}", form.boundary);
+        self.body(&content_type, form.finish())
+    }
+
+    /// Run the request through the app.
+    pub async fn send(self) -> TestResponse {
+        let Self {
+            client,
+            method,
+            uri,
+            mut headers,
+            cookies,
+            remote_addr,
+            conn,
+            body,
+        } = self;
+        if !cookies.is_empty() {
+            let cookie = HeaderValue::from_str(&cookies.join("; ")).expect("invalid cookie");
+            headers.insert(COOKIE, cookie);
+        }
+        let mut req = crate::http::Request::new(body);
+        *req.method_mut() = method;
+        *req.uri_mut() = uri;
+        *req.headers_mut() = headers;
+        let mut service = client.service.clone();
+        if let Some(addr) = remote_addr {
+            service = service.with_remote_addr(addr);
+        }
+        if let Some(info) = conn {
+            service = service.with_conn_info(info);
+        }
+        service.serve(Request::from(req)).await.into()
+    }
+}
+
+impl TestResponse {
+    /// Get a header value as str.
+    /// Return `None` if the header does not exist or is not visible ASCII.
+    pub fn header(&self, name: impl AsHeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Get the content type.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.headers.typed_get()
+    }
+
+    /// Assert status code.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the status code is not expected.
+    #[track_caller]
+    pub fn assert_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(status, self.status, "unexpected status code");
+        self
+    }
+
+    /// Assert a header has expected value.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the header does not exist or has an unexpected value.
+    #[track_caller]
+    pub fn assert_header(&self, name: impl AsHeaderName, value: &str) -> &Self {
+        let name = name.as_str().to_owned();
+        match self.header(name.as_str()) {
+            Some(actual) => assert_eq!(value, actual, "unexpected value of header `{}`", name),
+            None => panic!("header `{}` does not exist", name),
+        }
+        self
+    }
+
+    /// Assert a header does not exist.
+    ///
+    /// ### Panics
+    ///
+    /// Panics if the header exists.
+    #[track_caller]
+    pub fn assert_no_header(&self, name: impl AsHeaderName) -> &Self {
+        let name = name.as_str().to_owned();
+        assert!(
+            !self.headers.contains_key(name.as_str()),
+            "header `{}` exists",
+            name
+        );
+        self
+    }
+
+    /// Read body as bytes.
+    pub async fn bytes(self) -> io::Result<Bytes> {
+        let data: BytesMut = self
+            .body
+            .try_fold(BytesMut::new(), |mut data, chunk| async move {
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            })
+            .await?;
+        Ok(data.freeze())
+    }
+
+    /// Read body as utf-8 text.
+    pub async fn text(self) -> io::Result<String> {
+        let data = self.bytes().await?;
+        String::from_utf8(data.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Read body as json.
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "docs", doc(cfg(feature = "json")))]
+    pub async fn json<T: serde::de::DeserializeOwned>(self) -> io::Result<T> {
+        let data = self.bytes().await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+impl From<Response> for TestResponse {
+    #[inline]
+    fn from(resp: Response) -> Self {
+        let Response {
+            status,
+            version,
+            headers,
+            body,
+        } = resp;
+        Self {
+            status,
+            version,
+            headers,
+            body,
+        }
+    }
+}
+
+impl Multipart {
+    /// Construct an empty multipart form.
+    pub fn new() -> Self {
+        Self {
+            boundary: format!("roa-test-boundary-{:016x}", rand_u64()),
+            data: BytesMut::new(),
+        }
+    }
+
+    /// Append a text field.
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.part(
+            &format!("Content-Disposition: form-data; name=\"{}\"", name),
+            value.as_bytes(),
+        );
+        self
+    }
+
+    /// Append a file field.
+    pub fn file(mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) -> Self {
+        self.part(
+            &format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}",
+                name, filename, content_type
+            ),
+            data,
+        );
+        self
+    }
+
+    fn part(&mut self, headers: &str, data: &[u8]) {
+        self.data
+            .extend_from_slice(format!("--{}\r\n{}\r\n\r\n", self.boundary, headers).as_bytes());
+        self.data.extend_from_slice(data);
+        self.data.extend_from_slice(b"\r\n");
+    }
+
+    fn finish(mut self) -> Bytes {
+        self.data
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        self.data.freeze()
+    }
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A cheap random number for multipart boundary.
+fn rand_u64() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::net::SocketAddr;
+
+    use super::{Multipart, TestClient};
+    use crate::http::header::{CONTENT_TYPE, COOKIE};
+    use crate::http::{Method, StatusCode};
+    use crate::{App, Context};
+
+    async fn echo(ctx: &mut Context) -> crate::Result {
+        let method = ctx.method().to_string();
+        ctx.resp.headers.insert("x-method", method.parse()?);
+        ctx.resp
+            .headers
+            .insert("x-remote", ctx.remote_addr.to_string().parse()?);
+        if let Some(cookie) = ctx.get(COOKIE) {
+            ctx.resp.headers.insert("x-cookie", cookie.parse()?);
+        }
+        if let Some(content_type) = ctx.get(CONTENT_TYPE) {
+            ctx.resp.headers.insert(CONTENT_TYPE, content_type.parse()?);
+        }
+        let body = ctx.req.stream();
+        ctx.resp.write_stream(body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn request() -> Result<(), Box<dyn Error>> {
+        let client = TestClient::new(App::new().end(echo));
+        let addr: SocketAddr = "10.0.0.1:8000".parse()?;
+        let resp = client
+            .request(Method::PUT, "/")
+            .remote_addr(addr)
+            .cookie("name", "Hexilee")
+            .cookie("id", "1")
+            .text("Hello, World!")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::OK)
+            .assert_header("x-method", "PUT")
+            .assert_header("x-remote", "10.0.0.1:8000")
+            .assert_header("x-cookie", "name=Hexilee; id=1")
+            .assert_header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .assert_no_header("x-none");
+        assert_eq!("Hello, World!", resp.text().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn multipart() -> Result<(), Box<dyn Error>> {
+        let client = TestClient::new(App::new().end(echo));
+        let form = Multipart::new().text("name", "Hexilee").file(
+            "file",
+            "a.txt",
+            "text/plain",
+            b"content",
+        );
+        let resp = client.post("/").multipart(form).send().await;
+        let content_type = resp.header(CONTENT_TYPE).unwrap().to_string();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap()
+            .to_string();
+        let text = resp.text().await?;
+        assert!(text.starts_with(&format!("--{}\r\n", boundary)));
+        assert!(text.contains("name=\"name\"\r\n\r\nHexilee\r\n"));
+        assert!(text.contains("filename=\"a.txt\"\r\nContent-Type: text/plain\r\n\r\ncontent\r\n"));
+        assert!(text.ends_with(&format!("--{}--\r\n", boundary)));
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn json() -> Result<(), Box<dyn Error>> {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+        struct User {
+            id: u64,
+            name: String,
+        }
+
+        let client = TestClient::new(App::new().end(echo));
+        let user = User {
+            id: 0,
+            name: "Hexilee".into(),
+        };
+        let resp = client.post("/").json(&user).send().await;
+        resp.assert_header(CONTENT_TYPE, "application/json");
+        assert_eq!(user, resp.json::<User>().await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+//! This module provides a middleware `Timeout`,
+//! which cancels the downstream middlewares after a deadline.
+//!
+//! It works on any runtime, the timer is provided by `Spawn::sleep`.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::timeout::Timeout;
+//! use roa::http::StatusCode;
+//! use roa::test::TestClient;
+//! use roa::{App, Context};
+//! use std::time::Duration;
+//!
+//! async fn end(ctx: &mut Context) -> roa::Result {
+//!     // the time left can be used as timeout of outbound calls
+//!     let remaining = ctx.remaining().unwrap();
+//!     ctx.exec.sleep(Duration::from_secs(1)).await;
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let app = App::new().gate(Timeout::new(Duration::from_millis(10))).end(end);
+//!     let resp = TestClient::new(app).get("/").send().await;
+//!     resp.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use futures::future::{select, Either};
+
+use crate::http::StatusCode;
+use crate::{Context, Middleware, Next, Result, Status};
+
+/// A middleware to cancel the downstream middlewares after a duration,
+/// responding 503 SERVICE UNAVAILABLE by default.
+///
+/// The deadline is stored in context, readable by `Context::deadline` and `Context::remaining`.
+/// Nested timeouts never extend the deadline set by outer ones,
+/// and the outer deadline is restored once the downstream middlewares return.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout {
+    duration: Duration,
+    status_code: StatusCode,
+}
+
+impl Timeout {
+    /// Construct a timeout middleware.
+    #[inline]
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// Set the status code responded on timeout, like 504 GATEWAY TIMEOUT.
+    #[inline]
+    pub fn status(mut self, status_code: StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+}
+
+impl<'a, S> Middleware<'a, S> for Timeout {
+    #[inline]
+    async fn handle(&'a self, ctx: &'a mut Context<S>, next: Next<'a>) -> Result {
+        let now = Instant::now();
+        let outer = ctx.deadline();
+        let duration = match outer {
+            Some(outer) => self.duration.min(outer.saturating_duration_since(now)),
+            None => self.duration,
+        };
+        ctx.set_deadline(now + duration);
+        let sleep = ctx.exec.sleep(duration);
+        let result = match select(next, sleep).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(Status::new(
+                self.status_code,
+                format!("request timed out after {:?}", duration),
+                true,
+            )),
+        };
+        ctx.set_deadline(outer);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Timeout;
+    use crate::http::StatusCode;
+    use crate::test::TestClient;
+    use crate::{App, Context, MiddlewareExt};
+
+    async fn slow(ctx: &mut Context) -> crate::Result {
+        ctx.exec.sleep(Duration::from_millis(500)).await;
+        ctx.resp.write("done");
+        Ok(())
+    }
+
+    async fn remaining(ctx: &mut Context) -> crate::Result {
+        let remaining = ctx.remaining().expect("no deadline");
+        assert!(remaining <= Duration::from_millis(100));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn timeout() -> Result<(), Box<dyn std::error::Error>> {
+        let app = App::new()
+            .gate(Timeout::new(Duration::from_millis(50)))
+            .end(slow);
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!("request timed out after 50ms", resp.text().await?);
+
+        let app = App::new()
+            .gate(Timeout::new(Duration::from_secs(5)))
+            .end(slow);
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!("done", resp.text().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn gateway_timeout() {
+        let timeout = Timeout::new(Duration::from_millis(50)).status(StatusCode::GATEWAY_TIMEOUT);
+        let resp = TestClient::new(App::new().gate(timeout).end(slow))
+            .get("/")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn nested_deadline() {
+        let inner = Timeout::new(Duration::from_secs(5)).end(remaining);
+        let app = App::new()
+            .gate(Timeout::new(Duration::from_millis(100)))
+            .end(inner);
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn restore_deadline() {
+        async fn clear(ctx: &mut Context, next: crate::Next<'_>) -> crate::Result {
+            next.await?;
+            assert!(ctx.deadline().is_none());
+            Ok(())
+        }
+        let app = App::new()
+            .gate(clear)
+            .gate(Timeout::new(Duration::from_secs(5)))
+            .end(());
+        TestClient::new(app)
+            .get("/")
+            .send()
+            .await
+            .assert_status(StatusCode::OK);
+
+        async fn keep(ctx: &mut Context, next: crate::Next<'_>) -> crate::Result {
+            let deadline = ctx.deadline();
+            assert!(deadline.is_some());
+            next.await?;
+            assert_eq!(deadline, ctx.deadline());
+            Ok(())
+        }
+        let app = App::new()
+            .gate(Timeout::new(Duration::from_secs(5)))
+            .gate(keep)
+            .gate(Timeout::new(Duration::from_secs(1)))
+            .end(());
+        TestClient::new(app)
+            .get("/")
+            .send()
+            .await
+            .assert_status(StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn effective_duration() -> Result<(), Box<dyn std::error::Error>> {
+        async fn expire(ctx: &mut Context, next: crate::Next<'_>) -> crate::Result {
+            ctx.set_deadline(Instant::now());
+            next.await
+        }
+        let app = App::new()
+            .gate(expire)
+            .gate(Timeout::new(Duration::from_secs(5)))
+            .end(slow);
+        let resp = TestClient::new(app).get("/").send().await;
+        resp.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!("request timed out after 0ns", resp.text().await?);
+        Ok(())
+    }
+}
@@ -4,13 +4,13 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{self, Context, Poll};
 
-use futures::Future;
+use futures::{Future, FutureExt};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio_rustls::server::TlsStream;
 use tokio_rustls::TlsAcceptor;
 
 use super::ServerConfig;
-use crate::{Accept, AddrStream};
+use crate::{Accept, AddrStream, TlsInfo};
 
 /// A stream of connections based on another stream.
 /// As an implementation of roa_core::Accept.
@@ -115,6 +115,19 @@ where
     }
 }
 
+/// Collect TLS info of a connection after handshake.
+fn tls_info<IO>(stream: &TlsStream<IO>) -> TlsInfo {
+    let (_, conn) = stream.get_ref();
+    TlsInfo {
+        alpn_protocol: conn.alpn_protocol().map(ToOwned::to_owned),
+        server_name: conn.sni_hostname().map(ToOwned::to_owned),
+        peer_certificates: conn
+            .peer_certificates()
+            .map(|certs| certs.iter().map(|cert| cert.0.clone()).collect())
+            .unwrap_or_default(),
+    }
+}
+
 impl<I> TlsIncoming<I> {
     /// Construct from inner incoming.
     pub fn new(incoming: I, config: ServerConfig) -> Self {
@@ -156,12 +169,20 @@ where
                 Some(Ok(AddrStream {
                     stream,
                     remote_addr,
+                    info,
                 })) => {
-                    let accept_future = self.acceptor.accept(stream);
+                    let conn_info = info.clone();
+                    let accept_future = self.acceptor.accept(stream).map(move |result| {
+                        if let Ok(stream) = &result {
+                            let _ = conn_info.set_tls(tls_info(stream));
+                        }
+                        result
+                    });
                     Some(Ok(AddrStream::new(
                         remote_addr,
                         Handshaking(Box::new(accept_future)),
-                    )))
+                    )
+                    .with_info(info)))
                 }
                 Some(Err(err)) => Some(Err(err)),
                 None => None,
@@ -73,8 +73,8 @@ pub trait TlsListener {
 
 impl<S, E> TlsListener for App<S, Arc<E>>
 where
-    S: State,
-    E: for<'a> Endpoint<'a, S>,
+    S: State + Send + Sync,
+    E: Send + Sync + for<'a> Endpoint<'a, S>,
 {
     type Server = Server<TlsIncoming<TcpIncoming>, Self, Executor>;
     fn bind_tls(
@@ -84,7 +84,7 @@ where
     ) -> std::io::Result<(SocketAddr, Self::Server)> {
         let incoming = TlsIncoming::bind(addr, config)?;
         let local_addr = incoming.local_addr();
-        Ok((local_addr, self.accept(incoming)))
+        Ok((local_addr, self.try_accept(incoming)?))
     }
 
     fn listen_tls(
@@ -121,6 +121,10 @@ mod tests {
     use crate::{App, Context, Status};
 
     async fn end(ctx: &mut Context) -> Result<(), Status> {
+        let tls = ctx.conn.tls().expect("not a tls connection");
+        assert_eq!(Some("localhost"), tls.server_name.as_deref());
+        assert!(tls.peer_certificates.is_empty());
+        assert!(ctx.conn.local_addr().is_some());
         ctx.resp.write("Hello, World!");
         Ok(())
     }
@@ -0,0 +1,341 @@
+//! This module provides a middleware `trace`,
+//! opening a `tracing` span for each request and propagating
+//! [W3C trace context](https://www.w3.org/TR/trace-context/).
+//!
+//! The span is named `request`, with fields `method`, `path`, `route`, `status`, `latency_ms`,
+//! `trace_id`, `span_id` and `request_id`. Tasks spawned by `Context::exec` run in this span,
+//! so do blocking works of `roa-diesel`, and logs of `roa::logger` if it's used after `trace`.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use roa::trace::{trace, TraceContext, TRACEPARENT};
+//! use roa::{App, Context};
+//! use roa::http::StatusCode;
+//! use roa::test::TestClient;
+//!
+//! async fn end(ctx: &mut Context) -> roa::Result {
+//!     let trace_id = ctx.ext::<TraceContext>().unwrap().trace_id().to_string();
+//!     ctx.resp.write(trace_id);
+//!     Ok(())
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let app = App::new().gate(trace).end(end);
+//!     let resp = TestClient::new(app)
+//!         .get("/")
+//!         .header(TRACEPARENT, "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+//!         .send()
+//!         .await;
+//!     resp.assert_status(StatusCode::OK);
+//!     assert_eq!("0af7651916cd43dd8448eb211c80319c", resp.text().await?);
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt::Write;
+use std::time::Instant;
+
+use tracing::field::Empty;
+use tracing::Instrument;
+
+use crate::http::header::{HeaderMap, HeaderValue};
+use crate::{Context, Next, RequestId, Result};
+
+/// The header of W3C trace parent.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// The header of W3C trace state.
+pub const TRACESTATE: &str = "tracestate";
+
+/// The flag of sampled trace.
+const SAMPLED: u8 = 0x01;
+
+/// W3C trace context of a request, stored as a context extension by `trace`.
+///
+/// The trace id and flags are inherited from the `traceparent` of request,
+/// or generated if it's missing or invalid; the span id is always generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: String,
+    parent_id: Option<String>,
+    span_id: String,
+    flags: u8,
+    state: Option<String>,
+}
+
+impl TraceContext {
+    /// Construct a new root trace context, sampled.
+    pub fn new() -> Self {
+        Self {
+            trace_id: random_hex::<16>(),
+            parent_id: None,
+            span_id: random_hex::<8>(),
+            flags: SAMPLED,
+            state: None,
+        }
+    }
+
+    /// Construct a child trace context from headers,
+    /// or a new root one if `traceparent` is missing or invalid.
+    /// `tracestate` is kept only if `traceparent` is valid.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let parent = headers
+            .get(TRACEPARENT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_traceparent);
+        match parent {
+            None => Self::new(),
+            Some((trace_id, parent_id, flags)) => {
+                let state = headers
+                    .get_all(TRACESTATE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                Self {
+                    trace_id: trace_id.to_string(),
+                    parent_id: Some(parent_id.to_string()),
+                    span_id: random_hex::<8>(),
+                    flags,
+                    state: Some(state).filter(|state| !state.is_empty()),
+                }
+            }
+        }
+    }
+
+    /// The trace id, 32 lowercase hex digits.
+    #[inline]
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// The span id of the caller, if `traceparent` is valid.
+    #[inline]
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
+    /// The span id of this request, 16 lowercase hex digits.
+    #[inline]
+    pub fn span_id(&self) -> &str {
+        &self.span_id
+    }
+
+    /// Whether the trace is sampled.
+    #[inline]
+    pub fn sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+
+    /// The trace state, if any.
+    #[inline]
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// Format `traceparent` with this span as the parent.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// Insert `traceparent` and `tracestate` into headers,
+    /// to propagate this trace to a downstream service or the client.
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        let traceparent = HeaderValue::from_str(&self.traceparent())
+            .expect("traceparent must be a valid header value");
+        headers.insert(TRACEPARENT, traceparent);
+        match self.state.as_deref().map(HeaderValue::from_str) {
+            Some(Ok(state)) => {
+                headers.insert(TRACESTATE, state);
+            }
+            _ => {
+                headers.remove(TRACESTATE);
+            }
+        }
+    }
+}
+
+impl Default for TraceContext {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A middleware opening a span for each request, and propagating trace context.
+///
+/// The trace context is stored as an extension and injected into response headers.
+/// It records `request_id` if it's set by a previous middleware, like `roa::request_id`,
+/// and `route` if the request is routed by `roa::router`.
+pub async fn trace<S>(ctx: &mut Context<S>, next: Next<'_>) -> Result {
+    let trace_context = TraceContext::from_headers(&ctx.req.headers);
+    let span = tracing::info_span!(
+        "request",
+        method = %ctx.method(),
+        path = ctx.uri().path(),
+        route = Empty,
+        status = Empty,
+        latency_ms = Empty,
+        trace_id = trace_context.trace_id(),
+        span_id = trace_context.span_id(),
+        request_id = ctx.request_id().map(RequestId::as_str),
+    );
+    trace_context.inject(&mut ctx.resp.headers);
+    ctx.insert_ext(trace_context);
+
+    let start = Instant::now();
+    let result = next.instrument(span.clone()).await;
+    #[cfg(feature = "router")]
+    if let Some(route) = ctx.ext::<crate::router::MatchedRoute>() {
+        span.record("route", route.as_str());
+    }
+    let status = match &result {
+        Ok(()) => ctx.status(),
+        Err(status) => status.status_code,
+    };
+    span.record("status", status.as_u16());
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    result
+}
+
+/// Parse `traceparent` into trace id, parent id and flags.
+fn parse_traceparent(value: &str) -> Option<(&str, &str, u8)> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+    let valid = is_hex(version, 2)
+        && version != "ff"
+        // version 00 has exactly four parts, future versions may have more.
+        && (version != "00" || parts.next().is_none())
+        && is_hex(trace_id, 32)
+        && trace_id.bytes().any(|byte| byte != b'0')
+        && is_hex(parent_id, 16)
+        && parent_id.bytes().any(|byte| byte != b'0')
+        && is_hex(flags, 2);
+    if !valid {
+        return None;
+    }
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some((trace_id, parent_id, flags))
+}
+
+/// Whether a str is lowercase hex digits of the length.
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len
+        && value
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Generate N random bytes as lowercase hex digits, not all zeros.
+fn random_hex<const N: usize>() -> String {
+    let mut bytes = [0u8; N];
+    while bytes.iter().all(|byte| *byte == 0) {
+        bytes = std::array::from_fn(|_| rand::random());
+    }
+    let mut hex = String::with_capacity(N * 2);
+    for byte in bytes {
+        write!(hex, "{:02x}", byte).expect("fail to write string");
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_traceparent, trace, TraceContext, TRACEPARENT, TRACESTATE};
+    use crate::http::header::HeaderMap;
+    use crate::http::StatusCode;
+    use crate::test::TestClient;
+    use crate::{App, Context};
+
+    const PARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Some(("0af7651916cd43dd8448eb211c80319c", "b7ad6b7169203331", 1)),
+            parse_traceparent(PARENT)
+        );
+        // future version
+        assert!(
+            parse_traceparent("01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-extra")
+                .is_some()
+        );
+        for invalid in [
+            "",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c8031-b7ad6b7169203331-01",
+        ] {
+            assert!(parse_traceparent(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn from_headers() {
+        let root = TraceContext::from_headers(&HeaderMap::new());
+        assert_eq!(32, root.trace_id().len());
+        assert_eq!(16, root.span_id().len());
+        assert!(root.parent_id().is_none());
+        assert!(root.sampled());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT, PARENT.parse().unwrap());
+        headers.append(TRACESTATE, "a=1".parse().unwrap());
+        headers.append(TRACESTATE, "b=2".parse().unwrap());
+        let child = TraceContext::from_headers(&headers);
+        assert_eq!("0af7651916cd43dd8448eb211c80319c", child.trace_id());
+        assert_eq!(Some("b7ad6b7169203331"), child.parent_id());
+        assert_ne!("b7ad6b7169203331", child.span_id());
+        assert_eq!(Some("a=1,b=2"), child.state());
+
+        let mut headers = HeaderMap::new();
+        child.inject(&mut headers);
+        assert_eq!(
+            format!("00-0af7651916cd43dd8448eb211c80319c-{}-01", child.span_id()),
+            headers[TRACEPARENT]
+        );
+        assert_eq!("a=1,b=2", headers[TRACESTATE]);
+    }
+
+    #[tokio::test]
+    async fn propagate() -> Result<(), Box<dyn std::error::Error>> {
+        async fn end(ctx: &mut Context) -> crate::Result {
+            let span_id = ctx.ext::<TraceContext>().unwrap().span_id().to_string();
+            ctx.resp.write(span_id);
+            Ok(())
+        }
+        let client = TestClient::new(App::new().gate(trace).end(end));
+        let resp = client
+            .get("/")
+            .header(TRACEPARENT, PARENT)
+            .header(TRACESTATE, "a=1")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::OK)
+            .assert_header(TRACESTATE, "a=1");
+        let traceparent = resp.header(TRACEPARENT).unwrap().to_string();
+        let span_id = resp.text().await?;
+        assert_eq!(
+            format!("00-0af7651916cd43dd8448eb211c80319c-{}-01", span_id),
+            traceparent
+        );
+
+        // new root
+        let resp = client.get("/").header(TRACEPARENT, "invalid").send().await;
+        resp.assert_status(StatusCode::OK)
+            .assert_no_header(TRACESTATE);
+        assert!(!resp
+            .header(TRACEPARENT)
+            .unwrap()
+            .contains("0af7651916cd43dd"));
+        Ok(())
+    }
+}
@@ -35,7 +35,7 @@ use tokio_tungstenite::WebSocketStream;
 
 use crate::http::header::UPGRADE;
 use crate::http::StatusCode;
-use crate::{async_trait, throw, Context, Endpoint, State, Status};
+use crate::{throw, Context, Endpoint, State, Status};
 
 /// An alias for WebSocketStream<Upgraded>.
 pub type SocketStream = WebSocketStream<Upgraded>;
@@ -143,10 +143,9 @@ where
     }
 }
 
-#[async_trait(?Send)]
 impl<'a, F, S, Fut> Endpoint<'a, S> for Websocket<F, S, Fut>
 where
-    S: State,
+    S: State + Send + Sync,
     F: 'static + Sync + Send + Fn(Context<S>, SocketStream) -> Fut,
     Fut: 'static + Send + Future<Output = ()>,
 {
@@ -0,0 +1,78 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use futures::executor::block_on;
+use roa::http::StatusCode;
+use roa::router::{get, Router};
+use roa::{App, Context, Endpoint, Next, Request};
+
+/// An allocator counting allocations while it's enabled.
+struct Counter;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ENABLED.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counter = Counter;
+
+async fn end(ctx: &mut Context) -> roa::Result {
+    ctx.resp.status = StatusCode::NO_CONTENT;
+    Ok(())
+}
+
+async fn gate(_ctx: &mut Context, next: Next<'_>) -> roa::Result {
+    next.await
+}
+
+/// Count allocations of serving a request, after a warm-up one.
+fn allocations(endpoint: impl for<'a> Endpoint<'a, ()>) -> usize {
+    let service = App::new().end(endpoint).http_service();
+    let serve = || {
+        let req = Request::default();
+        let service = service.clone();
+        ENABLED.store(true, Ordering::SeqCst);
+        let resp = block_on(service.serve(req));
+        ENABLED.store(false, Ordering::SeqCst);
+        assert_eq!(StatusCode::NO_CONTENT, resp.status);
+    };
+    serve();
+    ALLOCATIONS.store(0, Ordering::SeqCst);
+    serve();
+    ALLOCATIONS.load(Ordering::SeqCst)
+}
+
+// allocations are counted globally, so cases run in a single test.
+#[test]
+fn routed_allocations() -> Result<(), Box<dyn std::error::Error>> {
+    let routed = allocations(Router::new().on("/", end).routes("/")?);
+
+    // dispatching by method doesn't allocate
+    let dispatched = allocations(Router::new().on("/", get(end)).routes("/")?);
+    assert_eq!(routed, dispatched);
+
+    // neither does including a router without gates
+    let included = allocations(
+        Router::new()
+            .include("/", Router::new().on("/", end))
+            .routes("/")?,
+    );
+    assert_eq!(routed, included);
+
+    // gates box their future once
+    let gated = allocations(Router::new().gate(gate).on("/", end).routes("/")?);
+    assert_eq!(routed + 1, gated);
+    Ok(())
+}
@@ -17,6 +17,7 @@ async fn serve_static_file() -> Result<(), Box<dyn std::error::Error>> {
     let (addr, server) = app.run()?;
     spawn(server);
     let resp = reqwest::get(&format!("http://{}", addr)).await?;
+    assert_eq!(Some(7), resp.content_length());
     assert_eq!("Hexilee", resp.text().await?);
     Ok(())
 }
@@ -0,0 +1,105 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use roa::http::StatusCode;
+use roa::logger::logger;
+use roa::preload::*;
+use roa::router::Router;
+use roa::trace::trace;
+use roa::{App, Context};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Span, Subscriber};
+use tracing_subscriber::layer::{self, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// A message of event, with the name of its span.
+type Record = (String, Option<&'static str>);
+
+/// Messages of events, with names of their spans.
+static EVENTS: Lazy<Mutex<Vec<Record>>> = Lazy::new(Default::default);
+
+struct Capture;
+
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: layer::Context<'_, S>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        let span = ctx.event_span(event).map(|span| span.name());
+        EVENTS.lock().unwrap().push((message.0, span));
+    }
+}
+
+fn init() {
+    let subscriber = tracing_subscriber::registry().with(Capture);
+    // the global subscriber may be set by other tests
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+fn current_span() -> Option<&'static str> {
+    Span::current().metadata().map(|meta| meta.name())
+}
+
+async fn end(ctx: &mut Context) -> roa::Result {
+    assert_eq!(Some("request"), current_span());
+    let blocking = ctx.exec.spawn_blocking(current_span).await?;
+    let spawned = ctx.exec.spawn(async { current_span() }).await?;
+    ctx.resp.write(format!("{:?} {:?}", blocking, spawned));
+    Ok(())
+}
+
+#[tokio::test]
+async fn propagate_span() -> anyhow::Result<()> {
+    init();
+    let router = Router::new().on("/:id", end);
+    let (addr, server) = App::new().gate(trace).end(router.routes("/user")?).run()?;
+    tokio::task::spawn(server);
+    let resp = reqwest::get(&format!("http://{}/user/1", addr)).await?;
+    assert_eq!(StatusCode::OK, resp.status());
+    assert!(resp.headers().contains_key("traceparent"));
+    assert_eq!(r#"Some("request") Some("request")"#, resp.text().await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn log_in_span() -> anyhow::Result<()> {
+    init();
+    let (addr, server) = App::new().gate(trace).gate(logger).end("Hello").run()?;
+    tokio::task::spawn(server);
+    let resp = reqwest::get(&format!("http://{}/logger", addr)).await?;
+    assert_eq!(StatusCode::OK, resp.status());
+    assert_eq!("Hello", resp.text().await?);
+
+    let spans = || -> Vec<Option<&'static str>> {
+        EVENTS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(message, _)| message.contains("GET /logger"))
+            .map(|(_, span)| *span)
+            .collect()
+    };
+    // the response log is emitted by a blocking task
+    for _ in 0..100 {
+        if spans().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(vec![Some("request"), Some("request")], spans());
+    Ok(())
+}